@@ -9,6 +9,12 @@ use clap::{Parser, Subcommand, Args, ValueEnum, builder::PossibleValue};
 #[clap[about = "If you pipe data (doesn't matter what data) to this program and then select a name for that data and declare it size, it will be uploaded to Google Drive"]]
 #[clap[long_about = None]]
 pub struct Arguments {
+    /// Select the PROFILE, each profile has its own client secret, token, encryption keys and defaults (config.json)
+    #[clap(long)]
+    #[clap(value_name = "PROFILE")]
+    #[clap(env = crate::profile::PROFILE_ENV)]
+    pub profile: Option<String>,
+
     /// Select the FILE containing the client secret. If you don't have one go here\nhttps://console.developers.google.com/apis/credentials
     #[clap(long)]
     #[clap(value_name = "FILE")]
@@ -18,6 +24,40 @@ pub struct Arguments {
     #[clap(value_name = "FILE")]
     pub token: Option<String>,
 
+    /// Select the OAuth FLOW used, when no valid token exists.
+    /// redirect:    Opens a local HTTP server there the browser is redirected to (needs a browser on the same machine)
+    /// interactive: Prints a URL and asks for the code displayed in the browser (copy-paste)
+    /// device:      Prints a code and a URL, which can be opened on any device (requires an OAuth client ID of the type "TVs and Limited Input devices")
+    /// [default: redirect]
+    #[clap(value_enum)]
+    #[clap(long)]
+    #[clap(value_name = "FLOW")]
+    #[clap(verbatim_doc_comment)]
+    pub auth_flow: Option<ArgAuthFlow>,
+
+    /// Select the SCOPE of the access pipe2drive asks for.
+    /// full:    Access to all the files in Google Drive
+    /// file:    Only access to the files created (or opened) by pipe2drive
+    /// appdata: Only access to the hidden application data folder of pipe2drive (used as the default folder)
+    /// [default: full]
+    #[clap(value_enum)]
+    #[clap(long)]
+    #[clap(value_name = "SCOPE")]
+    #[clap(verbatim_doc_comment)]
+    pub scope: Option<ArgScope>,
+
+    /// Select the FILE containing a service account key, which is used instead of the client secret and token
+    /// (can also be provided as data with the environment variable: PIPE2DRIVE_SERVICE_ACCOUNT_DATA)
+    #[clap(long)]
+    #[clap(value_name = "FILE")]
+    #[clap(verbatim_doc_comment)]
+    pub service_account: Option<String>,
+
+    /// The EMAIL of the user the service account should impersonate (requires domain-wide delegation)
+    #[clap(long)]
+    #[clap(value_name = "EMAIL")]
+    pub impersonate: Option<String>,
+
     /// Select log LEVEL (can also configures with the environment variable: RUST_LOG)
     #[clap(value_enum)]
     #[clap(long)]
@@ -93,6 +133,81 @@ impl ValueEnum for ArgLogLevel {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArgAuthFlow {
+    Redirect,
+    Interactive,
+    Device,
+}
+
+impl ValueEnum for ArgAuthFlow {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ArgAuthFlow::Redirect,
+            ArgAuthFlow::Interactive,
+            ArgAuthFlow::Device,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ArgAuthFlow::Redirect => PossibleValue::new("redirect"),
+            ArgAuthFlow::Interactive => PossibleValue::new("interactive").aliases(["copy-paste"]),
+            ArgAuthFlow::Device => PossibleValue::new("device"),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArgScope {
+    Full,
+    File,
+    Appdata,
+}
+
+impl ValueEnum for ArgScope {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ArgScope::Full,
+            ArgScope::File,
+            ArgScope::Appdata,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ArgScope::Full => PossibleValue::new("full").aliases(["drive"]),
+            ArgScope::File => PossibleValue::new("file").aliases(["drive.file"]),
+            ArgScope::Appdata => PossibleValue::new("appdata").aliases(["drive.appdata"]),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArgShareRole {
+    Reader,
+    Commenter,
+    Writer,
+}
+
+impl ValueEnum for ArgShareRole {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            ArgShareRole::Reader,
+            ArgShareRole::Commenter,
+            ArgShareRole::Writer,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ArgShareRole::Reader => PossibleValue::new("reader"),
+            ArgShareRole::Commenter => PossibleValue::new("commenter"),
+            ArgShareRole::Writer => PossibleValue::new("writer"),
+        })
+    }
+}
+
 #[derive(Subcommand, Debug)]
 #[clap[about]]
 pub enum Commands {
@@ -104,6 +219,26 @@ pub enum Commands {
     #[command(arg_required_else_help = false)]
     List(List),
 
+    /// Find files matching the filters (split files are matched as one file)
+    #[command(arg_required_else_help = false)]
+    Find(Find),
+
+    /// Create a folder
+    #[command(arg_required_else_help = true)]
+    Mkdir(Mkdir),
+
+    /// Show the storage quota (usage and free space) of the account
+    #[command(visible_alias = "df")]
+    Quota,
+
+    /// Show the size of the files below a folder, summed for every sub folder
+    #[command(arg_required_else_help = false)]
+    Du(Du),
+
+    /// Show the tree of files and folders
+    #[command(arg_required_else_help = false)]
+    Tree(Tree),
+
     /// Download a file from Google Drive
     #[command(arg_required_else_help = true)]
     Download(Download),
@@ -111,30 +246,226 @@ pub enum Commands {
     /// Upload a file to Google Drive
     #[command(arg_required_else_help = true)]
     Upload(Upload),
+
+    /// Check the stored parts of a file (sizes, checksums and padding), and optionally that it can be decrypted.
+    /// For a deduplicated upload (--dedup), the chunks in the index are checked (exist, not trashed and sizes)
+    #[command(arg_required_else_help = true)]
+    #[command(verbatim_doc_comment)]
+    Verify(Verify),
+
+    /// Upload the files in a local folder there are new or changed to a folder in Google Drive file by file (mirroring the folder tree)
+    #[command(arg_required_else_help = true)]
+    Push(Push),
+
+    /// Download the files in a folder in Google Drive to a local folder file by file (mirroring the folder tree)
+    #[command(arg_required_else_help = true)]
+    Pull(Pull),
+
+    /// Delete a file (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Delete(Delete),
+
+    /// Delete the files matching a name template, there are not kept by the retention policy
+    #[command(arg_required_else_help = true)]
+    Prune(Prune),
+
+    /// Delete the chunks of deduplicated uploads (--dedup), there are not used by any index.
+    /// The chunks modified in the last 24 hours are kept, they may belong to an upload there is still running
+    #[command(arg_required_else_help = true)]
+    #[command(verbatim_doc_comment)]
+    Gc(Gc),
+
+    /// Move a file to another folder (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Mv(Mv),
+
+    /// Copy a file (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Cp(Cp),
+
+    /// Move a file to the trash (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Trash(Trash),
+
+    /// Restore a file from the trash (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Untrash(Untrash),
+
+    /// Rename a file (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Rename(Rename),
+
+    /// Set the description of a file (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Describe(Describe),
+
+    /// Share a file with others, and list or revoke the permissions (all the parts, if it was split into multiple files)
+    #[command(arg_required_else_help = true)]
+    Share(Share),
+
+    /// Manage the shared drives
+    #[command(arg_required_else_help = true)]
+    Drives(Drives),
+
+    /// Search the catalog of uploads (kept locally, no access to Google Drive is needed)
+    #[command(arg_required_else_help = true)]
+    Catalog(Catalog),
+
+    /// Manage the credentials (login, status and logout)
+    #[command(arg_required_else_help = true)]
+    Auth(Auth),
 }
 
 #[derive(Args, Debug)]
 pub struct Info {
-    /// Provided the ID of the content of that you want more info about
+    /// Provided the ID (or PATH) of the content of that you want more info about.
+    /// A PATH has to contain a '/', e.g. 'backups/db/2026-10-18.tar' or '/backups'
+    #[clap(verbatim_doc_comment)]
     #[clap(long)]
-    #[clap(value_name = "ID")]
+    #[clap(value_name = "ID|PATH")]
     pub id: String,
 }
 
 #[derive(Args, Debug)]
 pub struct List {
-    /// If a folder ID is provided the content of that folder will be listed, otherwise the content of 'My Drive' will be listed
+    /// If a folder ID (or PATH) is provided the content of that folder will be listed, otherwise the content of 'My Drive' will be listed
     #[clap(long)]
-    #[clap(value_name = "ID")]
+    #[clap(value_name = "ID|PATH")]
+    pub folder: Option<String>,
+
+    /// List every part of files split into multiple files, instead of one entry per file
+    #[clap(long)]
+    #[clap(conflicts_with = "recursive")]
+    pub parts: bool,
+
+    /// List the content of all the sub folders as well
+    #[clap(long)]
+    pub recursive: bool,
+
+    /// Only list files with the tag, can be used multiple times (all the tags have to match)
+    #[clap(long)]
+    #[clap(value_name = "KEY=VALUE")]
+    #[clap(conflicts_with = "recursive")]
+    pub tag: Vec<String>,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Find {
+    /// Only search in the folder with the ID (or PATH), otherwise all of Google Drive is searched (not recursive)
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub folder: Option<String>,
+
+    /// Only find files with a name matching the GLOB, the wildcards '*' and '?' are supported
+    #[clap(long)]
+    #[clap(value_name = "GLOB")]
+    pub name_glob: Option<String>,
+
+    /// Only find files last modified before the TIME.
+    /// Example: 2026-10-18, 2026-10-18T12:00:00Z or 30d (relative to now in h, d or w)
+    #[clap(long)]
+    #[clap(value_name = "TIME")]
+    #[clap(verbatim_doc_comment)]
+    pub modified_before: Option<String>,
+
+    /// Only find files larger than the SIZE, e.g. 100mib
+    #[clap(long)]
+    #[clap(value_name = "SIZE")]
+    pub larger_than: Option<String>,
+
+    /// Only find files of the MIME type, e.g. 'application/octet-stream', 'application/*' or 'folder'
+    #[clap(long)]
+    #[clap(value_name = "MIME")]
+    pub mime: Option<String>,
+
+    /// Find the trashed files instead
+    #[clap(long)]
+    pub trashed: bool,
+
+    /// Only find encrypted files (*.age)
+    #[clap(long)]
+    pub encrypted: bool,
+
+    /// Only find files with the tag, can be used multiple times (all the tags have to match)
+    #[clap(long)]
+    #[clap(value_name = "KEY=VALUE")]
+    pub tag: Vec<String>,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Mkdir {
+    /// The PATH of the folder, e.g. 'backups/host1/daily'
+    #[clap(value_name = "PATH")]
+    pub path: String,
+
+    /// Create the parent folders there does not exist, and do not fail if the folder already exists
+    #[clap(short, long)]
+    pub parents: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Du {
+    /// If a folder ID (or PATH) is provided the size of that folder will be shown, otherwise the size of 'My Drive'
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub folder: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Tree {
+    /// If a folder ID (or PATH) is provided the tree of that folder will be shown, otherwise the tree of 'My Drive' will be shown
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
     pub folder: Option<String>,
 }
 
 #[derive(Args, Debug)]
 pub struct Download {
-    /// Provided the ID of the file (or one of the split files) you want to download
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to download
     #[clap(long)]
-    #[clap(value_name = "ID")]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Verify {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to verify
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
     pub file: String,
+
+    /// Decrypt the file (without writing the decrypted data anywhere) to check the authentication tags of it,
+    /// the private key is needed
+    #[clap(long)]
+    #[clap(verbatim_doc_comment)]
+    pub decrypt: bool,
+
+    /// The catalog FILE the MD5 checksums of the parts are read from, if the file has no parity parts
+    /// [default: pipe2drive-catalog.jsonl in the profile]
+    #[clap(long)]
+    #[clap(value_name = "FILE")]
+    #[clap(verbatim_doc_comment)]
+    pub catalog: Option<String>,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -142,25 +473,47 @@ pub struct Upload {
     /// The SIZE of the data you want to upload.
     /// Example: 100mib, 1gb or 1048576 (aka. 1mib)
     /// Supported Sizes: b, kb, kib, mb, mib, gb, gib, tb & tib
+    /// (not needed when uploading a single file with --path, then the size of the file is used)
     #[clap(long)]
     #[clap(value_name = "SIZE")]
     #[clap(verbatim_doc_comment)]
-    pub size: String,
+    pub size: Option<String>,
 
-    /// The NAME of the file uploaded to Google Drive
+    /// Upload the file or folder at the PATH instead of the data from stdin,
+    /// a folder is uploaded as a tar archive, there is created while it is uploaded
     #[clap(long)]
+    #[clap(value_name = "PATH")]
+    #[clap(verbatim_doc_comment)]
+    pub path: Option<String>,
+
+    /// Compress the tar archive (--path) with gzip, a single file is then archived as well
+    #[clap(long)]
+    #[clap(requires = "path")]
+    pub gzip: bool,
+
+    /// The NAME of the file uploaded to Google Drive,
+    /// it may contain the date/time fields %Y, %m, %d, %H, %M, %S, %F and %T, e.g. 'db-%F.tar'
+    /// [default: the name of the PATH (with .tar or .tar.gz for an archive), otherwise 'Untitled']
+    #[clap(long)]
+    #[clap(verbatim_doc_comment)]
     #[clap(value_name = "NAME")]
-    #[clap(default_value = "Untitled")]
-    pub filename: String,
+    pub filename: Option<String>,
 
-    /// The ID of the folder where you want the file to be uploaded to.
+    /// The ID (or PATH) of the folder where you want the file to be uploaded to.
     /// If this is not defined, the file will be uploaded to 'My Drive'
     #[clap(long)]
-    #[clap(value_name = "ID")]
+    #[clap(visible_alias = "dest")]
+    #[clap(value_name = "ID|PATH")]
     #[clap(verbatim_doc_comment)]
     pub parent_folder: Option<String>,
 
-    /// If a file exists with the same name it will be replaced
+    /// Create the folders in the PATH of the parent folder (--dest), there does not exist.
+    /// The parent folder is then always a PATH, e.g. 'daily' is the folder 'daily' in 'My Drive'
+    #[clap(verbatim_doc_comment)]
+    #[clap(long)]
+    pub mkdir: bool,
+
+    /// If a file exists with the same name it will be replaced, after the new file is uploaded successfully
     #[clap(long)]
     pub replace: bool,
 
@@ -168,10 +521,70 @@ pub struct Upload {
     #[clap(long)]
     pub duplicate: bool,
 
+    /// After the upload, delete the files matching the NAME, there are not kept by the POLICY (see: prune --keep)
+    #[clap(long)]
+    #[clap(value_name = "POLICY")]
+    pub keep: Option<String>,
+
+    /// Do not check if there is enough free storage quota for the upload, before starting it
+    #[clap(long)]
+    pub no_quota_check: bool,
+
+    /// If the upload fails, keep the parts there was uploaded, instead of deleting them
+    #[clap(long)]
+    pub keep_partial: bool,
+
     /// Encrypt the context before uploading it to Google Drive
     #[clap(long)]
     pub encrypt: bool,
 
+    /// Split the data into chunks by the content (content-defined chunking), and only upload the chunks
+    /// there are not already in the chunk folder. The file is uploaded as an index of the chunks,
+    /// there is reassembled when it is downloaded (--size is not needed)
+    #[clap(long)]
+    #[clap(verbatim_doc_comment)]
+    pub dedup: bool,
+
+    /// The ID (or PATH) of the folder the chunks are stored in, the folders in the PATH there does not exist are created
+    /// [default: the folder '.chunks' in the folder of the upload]
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    #[clap(requires = "dedup")]
+    #[clap(verbatim_doc_comment)]
+    pub chunk_folder: Option<String>,
+
+    /// Upload K parity parts (Reed-Solomon) along with the parts of the file, any K missing or damaged parts
+    /// are rebuilt from the parity parts when the file is downloaded
+    #[clap(long)]
+    #[clap(value_name = "K")]
+    #[clap(default_value_t = 0)]
+    #[clap(conflicts_with = "dedup")]
+    #[clap(verbatim_doc_comment)]
+    pub parity: usize,
+
+    /// Tag the upload with KEY=VALUE (saved as a property of every part), can be used multiple times
+    #[clap(long)]
+    #[clap(value_name = "KEY=VALUE")]
+    pub tag: Vec<String>,
+
+    /// Do not add the upload to the catalog
+    #[clap(long)]
+    pub no_catalog: bool,
+
+    /// The catalog FILE the upload is added to [default: pipe2drive-catalog.jsonl in the profile]
+    #[clap(long)]
+    #[clap(value_name = "FILE")]
+    #[clap(conflicts_with = "no_catalog")]
+    pub catalog: Option<String>,
+
+    /// Mirror the catalog to the folder with the ID (or PATH) in Google Drive after the upload,
+    /// the folders in the PATH there does not exist are created. The host is added to the name of it
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    #[clap(conflicts_with = "no_catalog")]
+    #[clap(verbatim_doc_comment)]
+    pub catalog_dest: Option<String>,
+
     /// Uploading a test file consisting of the repeated text sequence '0123456789'
     #[clap(long)]
     pub testing: bool,
@@ -181,4 +594,406 @@ pub struct Upload {
     #[clap(value_name = "SIZE")]
     #[clap(default_value_t = String::from("100MiB"))]
     pub testing_size: String,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Push {
+    /// The local folder DIR, the files and folders in it are uploaded
+    #[clap(value_name = "DIR")]
+    pub dir: String,
+
+    /// The ID (or PATH) of the folder the files are uploaded to, the folders in the PATH there does not exist are created.
+    /// If this is not defined, the files will be uploaded to 'My Drive'
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    #[clap(verbatim_doc_comment)]
+    pub dest: Option<String>,
+
+    /// Files bigger than the SIZE are split into parts of the SIZE, e.g. 100mib
+    /// [default: every file is uploaded as a single part]
+    #[clap(long)]
+    #[clap(value_name = "SIZE")]
+    #[clap(verbatim_doc_comment)]
+    pub part_size: Option<String>,
+
+    /// Only upload the files matching the GLOB (the path or the name), can be used multiple times, e.g. '*.sql'
+    #[clap(long)]
+    #[clap(value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Do not upload the files and folders matching the GLOB (the path or the name), can be used multiple times, e.g. 'target'
+    #[clap(long)]
+    #[clap(value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// The number of files uploaded at the same time
+    #[clap(short, long)]
+    #[clap(value_name = "N")]
+    #[clap(default_value_t = 4)]
+    pub jobs: usize,
+
+    /// Encrypt the files before uploading them to Google Drive
+    #[clap(long)]
+    pub encrypt: bool,
+
+    /// Delete the files and folders in the destination folder (--dest), there does not exist locally.
+    /// The files excluded with --include/--exclude are not deleted
+    #[clap(long)]
+    #[clap(requires = "dest")]
+    #[clap(verbatim_doc_comment)]
+    pub delete: bool,
+
+    /// Move the files missing locally to the trash, instead of deleting them permanently
+    #[clap(long)]
+    #[clap(requires = "delete")]
+    pub trash: bool,
+
+    /// Only show the planned actions (the files there would be uploaded, updated or deleted)
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Pull {
+    /// The ID (or PATH) of the folder in Google Drive, the files and folders in it are downloaded
+    #[clap(value_name = "ID|PATH")]
+    pub folder: String,
+
+    /// The local folder DIR the files are downloaded to, it is created if it does not exist
+    #[clap(value_name = "DIR")]
+    pub dir: String,
+
+    /// Only download the files matching the GLOB (the path or the name), can be used multiple times, e.g. '*.sql'
+    #[clap(long)]
+    #[clap(value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Do not download the files and folders matching the GLOB (the path or the name), can be used multiple times, e.g. 'tmp'
+    #[clap(long)]
+    #[clap(value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// The number of files downloaded at the same time
+    #[clap(short, long)]
+    #[clap(value_name = "N")]
+    #[clap(default_value_t = 4)]
+    pub jobs: usize,
+
+    /// Use the shared drive with the NAME (or ID) instead of 'My Drive', paths are resolved from the root of it
+    #[clap(long)]
+    #[clap(value_name = "NAME|ID")]
+    pub drive: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Delete {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to delete
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// Move the file to the trash, instead of deleting it permanently
+    #[clap(long)]
+    pub trash: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Prune {
+    /// The ID (or PATH) of the folder containing the files, otherwise 'My Drive' is used
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub folder: Option<String>,
+
+    /// The name TEMPLATE of the files, the time of a file is read from the date/time fields in the name,
+    /// e.g. 'db-%F.tar' (%Y, %m, %d, %H, %M, %S, %F and %T are supported).
+    /// Without any fields, the files with exactly that name are pruned, using their modified time
+    #[clap(long)]
+    #[clap(value_name = "TEMPLATE")]
+    #[clap(verbatim_doc_comment)]
+    pub name: String,
+
+    /// The POLICY of the files to keep, a comma separated list of PERIOD=COUNT.
+    /// last:  The newest COUNT files
+    /// hourly, daily, weekly, monthly & yearly: The newest file of each of the newest COUNT periods
+    /// Example: last=3,daily=7,weekly=4,monthly=12
+    #[clap(long)]
+    #[clap(value_name = "POLICY")]
+    #[clap(verbatim_doc_comment)]
+    pub keep: String,
+
+    /// Only show the files there would be deleted
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Move the files to the trash, instead of deleting them permanently
+    #[clap(long)]
+    pub trash: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Gc {
+    /// The ID (or PATH) of the chunk folder, e.g. 'backups/db/.chunks'
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub chunk_folder: String,
+
+    /// Only show the chunks there would be deleted
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Move the chunks to the trash, instead of deleting them permanently
+    #[clap(long)]
+    pub trash: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Mv {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to move
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// The ID (or PATH) of the folder the file is moved to
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub folder: String,
+
+    /// Allow multiple files to have the same name in the folder
+    #[clap(long)]
+    pub duplicate: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Cp {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to copy
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// The ID (or PATH) of the folder the copy is created in, otherwise the folder of the file is used
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub folder: Option<String>,
+
+    /// The NAME of the copy, split files keeps their suffix (.000, .001, ...)
+    #[clap(long)]
+    #[clap(value_name = "NAME")]
+    pub name: Option<String>,
+
+    /// Allow multiple files to have the same name in the folder
+    #[clap(long)]
+    pub duplicate: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Trash {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to move to the trash
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+}
+
+#[derive(Args, Debug)]
+pub struct Untrash {
+    /// Provided the ID of the file (or one of the split files) you want to restore (trashed files cannot be found by PATH)
+    #[clap(long)]
+    #[clap(value_name = "ID")]
+    pub file: String,
+}
+
+#[derive(Args, Debug)]
+pub struct Rename {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to rename
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// The new NAME of the file, split files keeps their suffix (.000, .001, ...)
+    #[clap(long)]
+    #[clap(value_name = "NAME")]
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct Describe {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to describe
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// The TEXT of the description, if this is not defined, the description will be removed
+    #[clap(long)]
+    #[clap(value_name = "TEXT")]
+    pub description: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Share {
+    #[clap(subcommand)]
+    pub command: ShareCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ShareCommands {
+    /// Grant a user, group, domain or anyone with the link access to the file
+    Add(ShareAdd),
+
+    /// List the permissions of the file
+    List(ShareList),
+
+    /// Revoke a permission of the file
+    Revoke(ShareRevoke),
+}
+
+#[derive(Args, Debug)]
+pub struct ShareAdd {
+    /// Provided the ID (or PATH) of the file (or one of the split files) you want to share
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// The ROLE granted
+    #[clap(value_enum)]
+    #[clap(long)]
+    #[clap(value_name = "ROLE")]
+    #[clap(default_value = "reader")]
+    pub role: ArgShareRole,
+
+    #[clap(flatten)]
+    pub grantee: ShareGrantee,
+
+    /// Send a notification email to the user or group
+    #[clap(long)]
+    pub notify: bool,
+}
+
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct ShareGrantee {
+    /// Share the file with the user with the EMAIL
+    #[clap(long)]
+    #[clap(value_name = "EMAIL")]
+    pub user: Option<String>,
+
+    /// Share the file with the group with the EMAIL
+    #[clap(long)]
+    #[clap(value_name = "EMAIL")]
+    pub group: Option<String>,
+
+    /// Share the file with everyone in the DOMAIN, e.g. example.com
+    #[clap(long)]
+    #[clap(value_name = "DOMAIN")]
+    pub domain: Option<String>,
+
+    /// Share the file with anyone with the link
+    #[clap(long)]
+    pub anyone: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ShareList {
+    /// Provided the ID (or PATH) of the file (or one of the split files)
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ShareRevoke {
+    /// Provided the ID (or PATH) of the file (or one of the split files)
+    #[clap(long)]
+    #[clap(value_name = "ID|PATH")]
+    pub file: String,
+
+    /// The ID of the permission (see: share list), e.g. 'anyoneWithLink'
+    #[clap(long)]
+    #[clap(value_name = "ID")]
+    pub permission: String,
+}
+
+#[derive(Args, Debug)]
+pub struct Drives {
+    #[clap(subcommand)]
+    pub command: DrivesCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DrivesCommands {
+    /// List the shared drives and there ID
+    List,
+}
+
+#[derive(Args, Debug)]
+pub struct Catalog {
+    #[clap(subcommand)]
+    pub command: CatalogCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CatalogCommands {
+    /// List the uploads in the catalog matching the filters (oldest first)
+    Search(CatalogSearch),
+}
+
+#[derive(Args, Debug)]
+pub struct CatalogSearch {
+    /// Only list uploads with a name matching the GLOB, the wildcards '*' and '?' are supported
+    #[clap(long)]
+    #[clap(value_name = "GLOB")]
+    pub name_glob: Option<String>,
+
+    /// Only list uploads from the host with the NAME
+    #[clap(long)]
+    #[clap(value_name = "NAME")]
+    pub host: Option<String>,
+
+    /// Only list uploads uploaded after the TIME.
+    /// Example: 2026-10-18, 2026-10-18T12:00:00Z or 30d (relative to now in h, d or w)
+    #[clap(long)]
+    #[clap(value_name = "TIME")]
+    #[clap(verbatim_doc_comment)]
+    pub after: Option<String>,
+
+    /// Only list uploads uploaded before the TIME (same format as --after)
+    #[clap(long)]
+    #[clap(value_name = "TIME")]
+    pub before: Option<String>,
+
+    /// Only list uploads with the tag, can be used multiple times (all the tags have to match)
+    #[clap(long)]
+    #[clap(value_name = "KEY=VALUE")]
+    pub tag: Vec<String>,
+
+    /// The catalog FILE [default: pipe2drive-catalog.jsonl in the profile]
+    #[clap(long)]
+    #[clap(value_name = "FILE")]
+    pub catalog: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Auth {
+    #[clap(subcommand)]
+    pub command: AuthCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommands {
+    /// Log in (starts the OAuth flow, if there is no valid token) and show the status
+    Login,
+
+    /// Show the account, granted scopes, token expiry and storage quota
+    Status,
+
+    /// Revoke the token and delete the client token file
+    Logout,
 }
@@ -3,6 +3,7 @@ extern crate dirs;
 extern crate google_drive3 as drive3;
 
 use std::{env};
+use std::path::PathBuf;
 use std::process::exit;
 
 use drive3::hyper_rustls::HttpsConnectorBuilder;
@@ -13,20 +14,180 @@ use drive3::hyper::Client;
 use drive3::oauth2::parse_application_secret;
 use drive3::oauth2::read_application_secret;
 use drive3::oauth2::ApplicationSecret;
-use drive3::oauth2::{InstalledFlowAuthenticator, InstalledFlowReturnMethod};
+use drive3::oauth2::{parse_service_account_key, read_service_account_key, ServiceAccountKey};
+use drive3::oauth2::{DeviceFlowAuthenticator, InstalledFlowAuthenticator, InstalledFlowReturnMethod};
+use drive3::oauth2::ServiceAccountAuthenticator;
+use drive3::oauth2::authenticator::Authenticator;
+use drive3::oauth2::authenticator_delegate::{DeviceAuthResponse, DeviceFlowDelegate, InstalledFlowDelegate};
 
+use drive3::api::Scope;
+use drive3::hyper::{Body, Method, Request};
 use drive3::DriveHub;
-use tokio::io::AsyncWriteExt;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
 
+use super::drive;
 use super::misc;
 
 // Mainly use for testing.
 pub const CLIENT_SECRET_ENV: &'static str = "PIPE2DRIVE_CLIENT_SECRET_DATA";
 pub const CLIENT_TOKEN_ENV: &'static str = "PIPE2DRIVE_CLIENT_TOKEN_DATA";
+pub const SERVICE_ACCOUNT_ENV: &str = "PIPE2DRIVE_SERVICE_ACCOUNT_DATA";
 
 pub const CLIENT_SECRET_FILE: &'static str = "client_secret.json";
 pub const CLIENT_TOKEN_FILE: &'static str = "client_token.json";
 
+// Google's endpoint and grant type for the OAuth 2.0 device authorization grant (RFC 8628),
+// the defaults in yup-oauth2 points to the deprecated endpoint
+const GOOGLE_DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const GOOGLE_DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+const GOOGLE_TOKEN_INFO_URL: &str = "https://oauth2.googleapis.com/tokeninfo";
+const GOOGLE_REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AuthFlow {
+    // Starts a local HTTP server, which Google redirects the browser to (needs a browser on the same machine)
+    Redirect,
+    // Prints a URL and reads the code, which the browser displays, back in (copy-paste)
+    Interactive,
+    // Prints a user code and a URL and polls Google until the code have been entered on any device
+    Device,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessScope {
+    // Access to all the files in Google Drive
+    Full,
+    // Only access to the files created (or opened) by pipe2drive
+    File,
+    // Only access to the hidden application data folder of pipe2drive
+    Appdata,
+}
+
+// The ID alias of the hidden application data folder
+pub const APP_DATA_FOLDER: &str = "appDataFolder";
+
+// The scope is selected once at startup and used by every request in the drive module
+static ACCESS_SCOPE: OnceLock<AccessScope> = OnceLock::new();
+
+pub fn init_scope(access_scope: AccessScope) {
+    debug!("Access scope: {:?}", access_scope);
+    if ACCESS_SCOPE.set(access_scope).is_err() {
+        panic!("The access scope can only be selected once")
+    }
+}
+
+pub fn access_scope() -> AccessScope {
+    ACCESS_SCOPE.get().copied().unwrap_or(AccessScope::Full)
+}
+
+pub fn scope() -> Scope {
+    match access_scope() {
+        AccessScope::Full => Scope::Full,
+        AccessScope::File => Scope::File,
+        AccessScope::Appdata => Scope::Appdata,
+    }
+}
+
+// The shared drive (`--drive`) used instead of 'My Drive', selected once the name is resolved to an ID
+static SHARED_DRIVE: OnceLock<String> = OnceLock::new();
+
+pub fn init_shared_drive(drive_id: String) {
+    if SHARED_DRIVE.set(drive_id).is_err() {
+        panic!("The shared drive can only be selected once")
+    }
+}
+
+pub fn shared_drive() -> Option<&'static str> {
+    SHARED_DRIVE.get().map(|drive_id| drive_id.as_str())
+}
+
+// The folder used when no folder is selected, with the appdata scope 'My Drive' cannot be accessed,
+// and the root folder of a shared drive has the same ID as the shared drive
+pub fn default_folder() -> Option<String> {
+    match access_scope() {
+        AccessScope::Appdata => Some(APP_DATA_FOLDER.to_owned()),
+        _ => shared_drive().map(|drive_id| drive_id.to_owned()),
+    }
+}
+
+// With a limited scope, Google Drive answers with 403 (insufficient scopes) or 404 (not found),
+// when the file was not created by pipe2drive, so explain why, instead of just showing the error
+pub fn log_scope_hint(e: &drive3::Error) {
+    let access_scope = access_scope();
+    if access_scope == AccessScope::Full {
+        return;
+    }
+
+    let status_code = match e {
+        drive3::Error::BadRequest(value) => value["error"]["code"].as_u64(),
+        drive3::Error::Failure(response) => Some(response.status().as_u16() as u64),
+        _ => None,
+    };
+
+    if let Some(403) | Some(404) = status_code {
+        error!("pipe2drive is limited to the scope '{}', {} - Use '--scope full' if this operation needs access to other files",
+               scope().as_ref(),
+               match access_scope {
+                   AccessScope::Appdata => "which only gives access to the hidden application data folder",
+                   _ => "which only gives access to files created (or opened) by pipe2drive",
+               });
+    }
+}
+
+// The default delegates from yup-oauth2 prints to stdout, which would end up
+// in the data being piped out of pipe2drive, so everything is written to stderr instead
+struct StderrFlowDelegate;
+
+impl DeviceFlowDelegate for StderrFlowDelegate {
+    fn present_user_code<'a>(
+        &'a self,
+        device_auth_resp: &'a DeviceAuthResponse,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            eprintln!("Please enter the code {} at {} and grant access to pipe2drive",
+                      device_auth_resp.user_code, device_auth_resp.verification_uri);
+            eprintln!("Do not close pipe2drive until access have been granted or denied, \
+                       the code expires at {}", device_auth_resp.expires_at);
+        })
+    }
+}
+
+impl InstalledFlowDelegate for StderrFlowDelegate {
+    fn present_user_url<'a>(
+        &'a self,
+        url: &'a str,
+        need_code: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>> {
+        Box::pin(async move {
+            if !need_code {
+                eprintln!("Please direct your browser to {} and follow the instructions displayed there", url);
+                return Ok(String::new());
+            }
+
+            eprintln!("Please direct your browser to {}, follow the instructions and enter the code displayed here: ", url);
+
+            // When data is piped to pipe2drive, stdin is not available, so the code is read from the terminal
+            let mut user_input = String::new();
+            let read_result = if atty::is(atty::Stream::Stdin) {
+                tokio::io::BufReader::new(tokio::io::stdin()).read_line(&mut user_input).await
+            } else {
+                match tokio::fs::File::open("/dev/tty").await {
+                    Ok(tty) => tokio::io::BufReader::new(tty).read_line(&mut user_input).await,
+                    Err(e) => Err(e),
+                }
+            };
+            read_result.map_err(|e| format!("Failed at reading the code - Error: {}", e))?;
+
+            Ok(user_input.trim().to_owned())
+        })
+    }
+}
+
 // reads the provided example client secret, the quick and dirty way.
 async fn read_client_secret(file: Option<String>) -> ApplicationSecret {
     let client_secret_path = misc::config_file(file, CLIENT_SECRET_FILE);
@@ -38,9 +199,196 @@ async fn read_client_secret(file: Option<String>) -> ApplicationSecret {
     })
 }
 
+// Returns None, if neither the environment variable or a file is provided,
+// because then the OAuth flow for a user account is used instead
+async fn read_service_account(file: Option<String>) -> Option<ServiceAccountKey> {
+    if let Ok(data) = env::var(SERVICE_ACCOUNT_ENV) {
+        return Some(parse_service_account_key(data.as_bytes()).unwrap_or_else(|e| {
+            error!("Error reading the service account key from the environment variable ({SERVICE_ACCOUNT_ENV}) - Error: {e}");
+            exit(misc::EXIT_CODE_004)
+        }));
+    }
+
+    let service_account_path = PathBuf::from(file?);
+    Some(read_service_account_key(&service_account_path).await.unwrap_or_else(|err| {
+        error!("Failed at reading the service account key - Error: {err} - service_account_path: {:?}", &service_account_path);
+        exit(misc::EXIT_CODE_004)
+    }))
+}
+
 pub type HubType = DriveHub<HttpsConnector<HttpConnector>>;
 
-pub (crate) async fn auth(client_secret_file: Option<String>, client_token_file: Option<String>) -> HubType {
+#[derive(Serialize, Deserialize)]
+pub struct AuthStatus {
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub scopes: Vec<String>,
+    pub token_expiry: Option<String>,
+    pub storage_limit: Option<String>,
+    pub storage_usage: Option<String>,
+    pub storage_usage_in_drive: Option<String>,
+    pub storage_usage_in_drive_trash: Option<String>,
+}
+
+fn https_client() -> Client<HttpsConnector<HttpConnector>> {
+    Client::builder().build(
+        HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_only()
+            .enable_http2()
+            .build()
+    )
+}
+
+// Checks if there are any credentials, without starting an OAuth flow
+pub fn has_credentials(client_token_file: Option<String>, service_account_file: &Option<String>) -> bool {
+    service_account_file.is_some()
+        || env::var(SERVICE_ACCOUNT_ENV).is_ok()
+        || env::var(CLIENT_TOKEN_ENV).is_ok()
+        || misc::config_file(client_token_file, CLIENT_TOKEN_FILE).exists()
+}
+
+pub (crate) async fn auth(
+    client_secret_file: Option<String>,
+    client_token_file: Option<String>,
+    auth_flow: AuthFlow,
+    service_account_file: Option<String>,
+    subject: Option<String>,
+) -> HubType {
+    let auth = match read_service_account(service_account_file).await {
+        Some(service_account_key) => service_account_auth(service_account_key, subject).await,
+        None => user_auth(client_secret_file, client_token_file, auth_flow).await,
+    };
+
+    DriveHub::new(https_client(), auth)
+}
+
+// Will start the OAuth flow, if there is no valid token
+pub async fn access_token(hub: &HubType) -> String {
+    match hub.auth.get_token(&[scope().as_ref()]).await {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            error!("Failed at getting an access token - No token was returned");
+            exit(misc::EXIT_CODE_016)
+        }
+        Err(e) => {
+            error!("Failed at getting an access token - Error: {}", e);
+            exit(misc::EXIT_CODE_016)
+        }
+    }
+}
+
+pub async fn status(hub: &HubType) -> AuthStatus {
+    let token = access_token(hub).await;
+
+    // The token is sent in the body, so it does not end up in the URL (and in logs of proxies)
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(GOOGLE_TOKEN_INFO_URL)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(Body::from(format!("access_token={}", token)))
+        .unwrap();
+    let token_info = match hub.client.request(request).await {
+        Ok(response) => drive3::hyper::body::to_bytes(response.into_body()).await.ok()
+            .and_then(|body| serde_json::from_slice::<serde_json::Value>(&body).ok()),
+        Err(e) => {
+            warn!("Failed at getting info about the access token - Error: {}", e);
+            None
+        }
+    }.unwrap_or_default();
+
+    // The value of expires_in is a string, even though it is the number of seconds
+    let token_expiry = token_info["expires_in"].as_str()
+        .and_then(|expires_in| expires_in.parse::<i64>().ok())
+        .map(|expires_in| (chrono::Local::now() + chrono::Duration::seconds(expires_in)).to_rfc3339());
+    let scopes = token_info["scope"].as_str()
+        .map_or(vec![], |scope| scope.split(' ').map(|s| s.to_owned()).collect());
+
+    let about = drive::about(hub).await;
+    let user = about.user.unwrap_or_default();
+    let storage_quota = about.storage_quota.unwrap_or_default();
+
+    AuthStatus {
+        email: user.email_address.or(token_info["email"].as_str().map(|s| s.to_owned())),
+        display_name: user.display_name,
+        scopes,
+        token_expiry,
+        storage_limit: storage_quota.limit,
+        storage_usage: storage_quota.usage,
+        storage_usage_in_drive: storage_quota.usage_in_drive,
+        storage_usage_in_drive_trash: storage_quota.usage_in_drive_trash,
+    }
+}
+
+// Revokes the tokens at Google and deletes the client token file
+pub async fn logout(client_token_file: Option<String>) {
+    let client_token_path = misc::config_file(client_token_file, CLIENT_TOKEN_FILE);
+    if !client_token_path.exists() {
+        warn!("Not logged in, there is no client token file - Path: {:?}", &client_token_path);
+        return;
+    }
+
+    let data = tokio::fs::read(&client_token_path).await.unwrap_or_else(|err| {
+        error!("Failed at reading the client token file - Error: {err} - Path: {:?}", &client_token_path);
+        exit(misc::EXIT_CODE_004)
+    });
+    let tokens = serde_json::from_slice::<Vec<serde_json::Value>>(&data).unwrap_or_else(|err| {
+        warn!("The client token file is invalid, so the tokens cannot be revoked - Error: {err}");
+        vec![]
+    });
+
+    let client = https_client();
+    for token in tokens {
+        // Revoking the refresh token, also revokes the access tokens issued with it
+        let token = match token["token"]["refresh_token"].as_str().or(token["token"]["access_token"].as_str()) {
+            Some(t) => t.to_owned(),
+            None => continue,
+        };
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(GOOGLE_REVOKE_URL)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(Body::from(format!("token={}", token)))
+            .unwrap();
+        match client.request(request).await {
+            Ok(response) if response.status().is_success() => info!("Revoked the token"),
+            Ok(response) => warn!("Failed at revoking the token (it may already be expired or revoked) - Status: {}", response.status()),
+            Err(e) => warn!("Failed at revoking the token - Error: {}", e),
+        }
+    }
+
+    tokio::fs::remove_file(&client_token_path).await.unwrap_or_else(|err| {
+        error!("Failed at deleting the client token file - Error: {err} - Path: {:?}", &client_token_path);
+        exit(misc::EXIT_CODE_016)
+    });
+    info!("Deleted the client token file: {:?}", &client_token_path);
+}
+
+async fn service_account_auth(
+    service_account_key: ServiceAccountKey,
+    subject: Option<String>,
+) -> Authenticator<HttpsConnector<HttpConnector>> {
+    info!("Auth as the service account: {}", &service_account_key.client_email);
+
+    let mut builder = ServiceAccountAuthenticator::builder(service_account_key);
+    // Domain-wide delegation, the service account acts on behalf of the user (subject)
+    if let Some(subject) = subject {
+        info!("Impersonating the user: {}", &subject);
+        builder = builder.subject(subject);
+    }
+
+    builder.build().await.unwrap_or_else(|err| {
+        error!("Failed at auth to Google Drive with the service account - Error: {err}");
+        exit(misc::EXIT_CODE_005)
+    })
+}
+
+async fn user_auth(
+    client_secret_file: Option<String>,
+    client_token_file: Option<String>,
+    auth_flow: AuthFlow,
+) -> Authenticator<HttpsConnector<HttpConnector>> {
     let client_secret = match env::var(CLIENT_SECRET_ENV) {
         Ok(data) => {
             parse_application_secret(data.as_bytes()).unwrap_or_else(|e| {
@@ -66,22 +414,34 @@ pub (crate) async fn auth(client_secret_file: Option<String>, client_token_file:
         };
     }
 
-    let auth_result =
-        InstalledFlowAuthenticator::builder(client_secret, InstalledFlowReturnMethod::HTTPRedirect)
-            .persist_tokens_to_disk(&client_token_path)
-            .build()
-            .await;
-    let auth = auth_result.unwrap_or_else(|err| {
+    debug!("Auth flow: {:?}", auth_flow);
+    let auth_result = match auth_flow {
+        AuthFlow::Redirect => installed_flow(client_secret, InstalledFlowReturnMethod::HTTPRedirect, &client_token_path).await,
+        AuthFlow::Interactive => installed_flow(client_secret, InstalledFlowReturnMethod::Interactive, &client_token_path).await,
+        AuthFlow::Device => {
+            DeviceFlowAuthenticator::builder(client_secret)
+                .device_code_url(GOOGLE_DEVICE_CODE_URL)
+                .grant_type(GOOGLE_DEVICE_GRANT_TYPE)
+                .flow_delegate(Box::new(StderrFlowDelegate))
+                .persist_tokens_to_disk(&client_token_path)
+                .build()
+                .await
+        }
+    };
+    auth_result.unwrap_or_else(|err| {
         error!("Failed at auth to Google Drive - Error: {err} - client_token_path: {:?}", &client_token_path);
         exit(misc::EXIT_CODE_005)
-    });
+    })
+}
 
-    let client = Client::builder().build(
-        HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .https_only()
-            .enable_http2()
-            .build()
-    );
-    DriveHub::new(client, auth)
+async fn installed_flow(
+    client_secret: ApplicationSecret,
+    method: InstalledFlowReturnMethod,
+    client_token_path: &PathBuf,
+) -> std::io::Result<Authenticator<HttpsConnector<HttpConnector>>> {
+    InstalledFlowAuthenticator::builder(client_secret, method)
+        .flow_delegate(Box::new(StderrFlowDelegate))
+        .persist_tokens_to_disk(client_token_path)
+        .build()
+        .await
 }
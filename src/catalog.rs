@@ -0,0 +1,229 @@
+use crate::drive::UploadResult;
+use crate::misc;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+// The catalog is a JSON-lines file (one record per upload) in the profile, it is only appended to
+pub const CATALOG_FILE: &str = "pipe2drive-catalog.jsonl";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogPart {
+    pub id: String,
+    pub name: String,
+    pub size: u64,
+    pub md5: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogRecord {
+    pub name: String,
+    pub folder_id: Option<String>,
+    // The size of the data, without the filler bytes (0x00) at the end of the last part
+    pub size: u64,
+    pub parts: Vec<CatalogPart>,
+    pub encrypted: bool,
+    pub dedup: bool,
+    pub uploaded: DateTime<Utc>,
+    pub host: String,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl CatalogRecord {
+    // Only the parts of the file are recorded (not the parity parts, or the chunks of a deduplicated upload)
+    pub fn new(upload_result: &UploadResult,
+               name: &str,
+               folder_id: Option<String>,
+               encrypted: bool,
+               dedup: bool,
+               tags: BTreeMap<String, String>) -> CatalogRecord {
+        let part_regex = Regex::new(&format!(r#"^{}(\.[0-9]+)?$"#, regex::escape(name))).unwrap();
+        let files: Vec<_> = upload_result.uploaded_files.iter()
+            .filter(|file| file.name.as_deref().is_some_and(|file_name| part_regex.is_match(file_name)))
+            .collect();
+
+        let parts: Vec<CatalogPart> = files.iter()
+            .map(|file| CatalogPart {
+                id: file.id.clone().unwrap_or_default(),
+                name: file.name.clone().unwrap_or_default(),
+                size: file.size.as_ref().and_then(|size| size.parse::<u64>().ok()).unwrap_or(0),
+                md5: file.md5_checksum.clone(),
+            })
+            .collect();
+        let padding = files.last().map_or(0, |file| misc::parse_padding(&file.description).max(0) as u64);
+
+        CatalogRecord {
+            name: name.to_owned(),
+            folder_id,
+            size: upload_result.data_size
+                .unwrap_or_else(|| parts.iter().map(|part| part.size).sum::<u64>().saturating_sub(padding)),
+            parts,
+            encrypted,
+            dedup,
+            uploaded: Utc::now(),
+            host: host(),
+            tags,
+        }
+    }
+}
+
+pub fn host() -> String {
+    gethostname::gethostname().to_string_lossy().to_string()
+}
+
+// Every host uploads the catalog with its own name (e.g. pipe2drive-catalog-db1.jsonl), so the hosts sharing
+// a folder don't overwrite the catalogs of each other
+pub fn remote_name(path: &Path, host: &str) -> String {
+    let stem = path.file_stem().map_or("".into(), |stem| stem.to_string_lossy());
+    match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, host, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, host),
+    }
+}
+
+pub fn append(path: &Path, record: &CatalogRecord) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record).unwrap())
+}
+
+// Reads all the records, the lines there cannot be parsed are skipped (with a warning)
+pub fn load(path: &Path) -> io::Result<Vec<CatalogRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(fs::File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<CatalogRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => warn!("Skipping line {} of the catalog '{}' - {}", index + 1, path.to_string_lossy(), e),
+        }
+    }
+    Ok(records)
+}
+
+// Parses a tag in the format KEY=VALUE
+pub fn parse_tag(tag: &str) -> Result<(String, String), String> {
+    match tag.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("The tag '{}' is invalid, it has to be KEY=VALUE", tag)),
+    }
+}
+
+#[derive(Default)]
+pub struct CatalogFilter {
+    pub name: Option<Regex>,
+    pub host: Option<String>,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub tags: Vec<(String, String)>,
+}
+
+impl CatalogFilter {
+    pub fn matches(&self, record: &CatalogRecord) -> bool {
+        self.name.as_ref().is_none_or(|name| name.is_match(&record.name))
+            && self.host.as_ref().is_none_or(|host| host.eq_ignore_ascii_case(&record.host))
+            && self.after.is_none_or(|after| record.uploaded >= after)
+            && self.before.is_none_or(|before| record.uploaded < before)
+            && self.tags.iter().all(|(key, value)| record.tags.get(key) == Some(value))
+    }
+}
+
+pub fn search(records: Vec<CatalogRecord>, filter: &CatalogFilter) -> Vec<CatalogRecord> {
+    records.into_iter().filter(|record| filter.matches(record)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_tag, remote_name, search, CatalogFilter, CatalogRecord};
+    use crate::drive::UploadResult;
+    use google_drive3::api::File;
+    use chrono::{TimeZone, Utc};
+    use regex::Regex;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn record(name: &str, host: &str, day: u32, tags: &[(&str, &str)]) -> CatalogRecord {
+        CatalogRecord {
+            name: name.to_owned(),
+            folder_id: None,
+            size: 100,
+            parts: Vec::new(),
+            encrypted: false,
+            dedup: false,
+            uploaded: Utc.with_ymd_and_hms(2026, 10, day, 2, 0, 0).unwrap(),
+            host: host.to_owned(),
+            tags: tags.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<BTreeMap<String, String>>(),
+        }
+    }
+
+    #[test]
+    fn test_010_search() {
+        let records = vec![
+            record("db-2026-10-13.tar", "web1", 13, &[("env", "prod")]),
+            record("db-2026-10-14.tar", "web2", 14, &[]),
+            record("srv-2026-10-13.tar", "web1", 13, &[("env", "test")]),
+        ];
+
+        let filter = CatalogFilter {
+            host: Some("WEB1".to_owned()),
+            after: Some(Utc.with_ymd_and_hms(2026, 10, 13, 0, 0, 0).unwrap()),
+            before: Some(Utc.with_ymd_and_hms(2026, 10, 14, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(2, search(records.clone(), &filter).len());
+
+        let filter = CatalogFilter {
+            name: Some(Regex::new("^db-").unwrap()),
+            tags: vec![("env".to_owned(), "prod".to_owned())],
+            ..Default::default()
+        };
+        let found = search(records, &filter);
+        assert_eq!(1, found.len());
+        assert_eq!("db-2026-10-13.tar", found[0].name);
+    }
+
+    #[test]
+    fn test_020_parse_tag() {
+        assert_eq!(Ok(("env".to_owned(), "a=b".to_owned())), parse_tag("env=a=b"));
+        assert!(parse_tag("env").is_err());
+        assert!(parse_tag("=prod").is_err());
+    }
+
+    #[test]
+    fn test_030_remote_name() {
+        assert_eq!("pipe2drive-catalog-db1.jsonl", remote_name(Path::new("/etc/pipe2drive/pipe2drive-catalog.jsonl"), "db1"));
+        assert_eq!("catalog-db1", remote_name(Path::new("catalog"), "db1"));
+    }
+
+    #[test]
+    fn test_040_dedup_size() {
+        let index = File {
+            id: Some("1".to_owned()),
+            name: Some("db.sql".to_owned()),
+            size: Some("512".to_owned()),
+            ..Default::default()
+        };
+        let upload_result = UploadResult {
+            complete: true,
+            uploaded_files: vec![index],
+            deleted_files: vec![],
+            data_size: Some(10 * 1024 * 1024),
+            parity_failed: false,
+        };
+
+        // The size of a deduplicated upload is the size of the data, not of the index
+        let record = CatalogRecord::new(&upload_result, "db.sql", None, false, true, BTreeMap::new());
+        assert_eq!(10 * 1024 * 1024, record.size);
+        assert_eq!(1, record.parts.len());
+    }
+}
@@ -2,7 +2,11 @@ use crate::misc::config_file;
 use age::x25519::{Identity, Recipient};
 use secrecy::ExposeSecret;
 use std::fs;
+use std::io::{self, Read, Write};
+use std::iter;
 use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
 
 pub fn load_public_key(pub_file: Option<String>) -> Recipient {
     let public_key: Recipient;
@@ -70,3 +74,160 @@ pub fn load_private_key(file: Option<String>) -> Identity {
 
     return private_key;
 }
+
+// The size of the data after it is encrypted (rounded up), the header of age with one X25519
+// recipient is less than 256 bytes, and every chunk of 64 KiB gets a 16 bytes authentication tag
+pub fn encrypted_size(size: u64) -> u64 {
+    const CHUNK_SIZE: u64 = 64 * 1024;
+    size + 256 + 16 * (size / CHUNK_SIZE + 1)
+}
+
+pub fn encrypt(data: &[u8], public_key: &Recipient) -> io::Result<Vec<u8>> {
+    let mut encrypted = Vec::with_capacity(encrypted_size(data.len() as u64) as usize);
+    let mut writer = age::Encryptor::with_recipients(vec![Box::new(public_key.clone())])
+        .expect("An encryptor always has a recipient")
+        .wrap_output(&mut encrypted)
+        .map_err(io::Error::other)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(encrypted)
+}
+
+pub fn decrypt(data: &[u8], private_key: &Identity) -> io::Result<Vec<u8>> {
+    let decryptor = match age::Decryptor::new(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+        age::Decryptor::Recipients(decryptor) => decryptor,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "The data is encrypted with a passphrase")),
+    };
+
+    let mut decrypted = Vec::with_capacity(data.len());
+    decryptor.decrypt(iter::once(private_key as &dyn age::Identity))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        .read_to_end(&mut decrypted)?;
+    Ok(decrypted)
+}
+
+// Checks the authentication tags (MAC) of the encrypted data written to it, without keeping the decrypted data.
+// The data is decrypted in a thread, because the decryptor reads the data (instead of the data being written to it)
+pub struct DecryptVerifier {
+    sender: Option<SyncSender<Vec<u8>>>,
+    handle: JoinHandle<io::Result<u64>>,
+}
+
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() {
+            // The writer is dropped at the end of the data
+            match self.receiver.recv() {
+                Ok(data) => {
+                    self.buffer = data;
+                    self.position = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.buffer.len() - self.position);
+        buf[..len].copy_from_slice(&self.buffer[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl DecryptVerifier {
+    pub fn new(private_key: Identity) -> DecryptVerifier {
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(16);
+        let handle = thread::spawn(move || {
+            let reader = ChannelReader { receiver, buffer: Vec::new(), position: 0 };
+            let decryptor = match age::Decryptor::new(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+                age::Decryptor::Recipients(decryptor) => decryptor,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "The data is encrypted with a passphrase")),
+            };
+            let mut decrypted = decryptor.decrypt(iter::once(&private_key as &dyn age::Identity))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            io::copy(&mut decrypted, &mut io::sink())
+        });
+
+        DecryptVerifier { sender: Some(sender), handle }
+    }
+
+    // Returns the size of the decrypted data, or the error if the data could not be decrypted
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.sender = None;
+        self.handle.join().unwrap_or_else(|_| Err(io::Error::other("The decryption thread panicked")))
+    }
+}
+
+impl Write for DecryptVerifier {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // If the decryption has failed, the rest of the data is ignored (the error is returned by `finish`)
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(buf.to_vec());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The secret key of the keyed hashes the deduplicated chunks are named by, so the names does not reveal the content.
+// It is generated the first time it is used, and has to be copied to the other machines using the same chunks
+pub fn load_dedup_key() -> [u8; 32] {
+    let path = config_file(None, "pipe2drive-dedup.key");
+
+    let mut key = [0u8; 32];
+    if path.exists() {
+        let key_string = fs::read_to_string(path.as_path())
+            .unwrap_or_else(|e| panic!("Failed at reading the dedup key: {} - {}", path.to_str().unwrap(), e));
+        let key_string = key_string.trim();
+        if key_string.len() != 64 || !key_string.is_ascii() {
+            panic!("The dedup key is invalid (it should be 64 hex characters): {}", path.to_str().unwrap());
+        }
+        for (index, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&key_string[index * 2..index * 2 + 2], 16)
+                .unwrap_or_else(|_| panic!("The dedup key is invalid (it should be 64 hex characters): {}", path.to_str().unwrap()));
+        }
+        info!("Read the dedup key from: {}", path.to_str().unwrap());
+    } else {
+        getrandom::fill(&mut key).expect("Failed at generating the dedup key");
+        let key_string: String = key.iter().map(|byte| format!("{:02x}", byte)).collect();
+        fs::write(path.as_path(), key_string.as_bytes())
+            .unwrap_or_else(|e| panic!("Failed at writing the dedup key: {} - {}", path.to_str().unwrap(), e));
+        info!("Write the dedup key to: {}", path.to_str().unwrap());
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encrypt, DecryptVerifier};
+    use std::io::Write;
+
+    #[test]
+    fn test_010_decrypt_verifier() {
+        let private_key = age::x25519::Identity::generate();
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let encrypted = encrypt(&data, &private_key.to_public()).unwrap();
+
+        let mut verifier = DecryptVerifier::new(private_key.clone());
+        for chunk in encrypted.chunks(10_000) {
+            verifier.write_all(chunk).unwrap();
+        }
+        assert_eq!(data.len() as u64, verifier.finish().unwrap());
+
+        // A flipped bit fails the authentication tag
+        let mut damaged = encrypted.clone();
+        damaged[100_000] ^= 1;
+        let mut verifier = DecryptVerifier::new(private_key);
+        verifier.write_all(&damaged).unwrap();
+        assert!(verifier.finish().is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::misc;
+use google_drive3::api::About;
+use google_drive3::Result;
+use std::process::exit;
+
+pub const ABOUT_FIELDS: &str = "user(displayName,emailAddress),storageQuota";
+
+pub async fn get_about(hub: &HubType) -> Result<About> {
+    hub.about().get()
+        .param("fields", ABOUT_FIELDS)
+        .add_scope(auth::scope())
+        .doit()
+        .await
+        .map(|(_, about)| about)
+}
+
+pub async fn about(hub: &HubType) -> About {
+    get_about(hub).await.unwrap_or_else(|e| {
+        error!("Failed at getting info about the account - {}", e);
+        auth::log_scope_hint(&e);
+        exit(misc::EXIT_CODE_016);
+    })
+}
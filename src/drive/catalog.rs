@@ -0,0 +1,59 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::catalog;
+use crate::drive::info::FIELDS;
+use crate::drive::list;
+use google_drive3::api::File;
+use std::fs;
+use std::path::Path;
+
+const CATALOG_MIME_TYPE: &str = "application/x-ndjson";
+
+// Mirrors the local catalog to the folder, the catalog file of this host in the folder is replaced by the
+// local catalog (or created). If it fails, only the local catalog is updated
+pub async fn upload_catalog(hub: &HubType, path: &Path, folder_id: Option<String>) {
+    let name = catalog::remote_name(path, &catalog::host());
+    let data = match fs::File::open(path) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed at reading the catalog '{}' - {}", path.to_string_lossy(), e);
+            return;
+        }
+    };
+
+    let existing = list(hub, folder_id.clone()).await
+        .into_iter()
+        .find(|file| file.name.as_deref() == Some(name.as_str()));
+
+    let result = match existing {
+        Some(existing) => hub.files()
+            .update(File::default(), existing.id.as_ref().unwrap())
+            .supports_all_drives(true)
+            .param("fields", FIELDS)
+            .add_scope(auth::scope())
+            .upload_resumable(data, CATALOG_MIME_TYPE.parse().unwrap())
+            .await,
+        None => {
+            let req = File {
+                name: Some(name.clone()),
+                parents: folder_id.or_else(auth::default_folder).map(|id| vec![id]),
+                ..Default::default()
+            };
+            hub.files()
+                .create(req)
+                .supports_all_drives(true)
+                .param("fields", FIELDS)
+                .add_scope(auth::scope())
+                .upload_resumable(data, CATALOG_MIME_TYPE.parse().unwrap())
+                .await
+        }
+    };
+
+    match result {
+        Ok(_) => info!("Uploaded the catalog: '{}'", name),
+        Err(e) => {
+            error!("Failed at uploading the catalog '{}' - {}", name, e);
+            auth::log_scope_hint(&e);
+        }
+    }
+}
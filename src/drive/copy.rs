@@ -0,0 +1,57 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::info::FIELDS;
+use crate::drive::list::{check_name_free, create_file_list, create_parity_list};
+use crate::drive::parity;
+use crate::misc;
+use google_drive3::api::File;
+use std::process::exit;
+
+// Copies all the parts of a file, which was split into multiple files, and the parity parts of it server-side into the folder
+// (`None` is the folder of the file), optionally with a new name (the parts keeps their suffix).
+// The descriptions are copied as well, because the last part holds the number of filler bytes.
+// Stops if the folder already has a file with the name, unless DUPLICATE is set
+pub async fn copy_all(hub: &HubType, file: &File, folder_id: Option<String>, new_name: Option<&str>, duplicate: bool) -> Vec<File> {
+    let mut files = create_file_list(hub, file).await;
+    let part_count = files.len();
+    let target_folder = folder_id.clone().or_else(|| file.parents.as_ref().and_then(|parents| parents.first().cloned()));
+    let target_name = new_name.unwrap_or_else(|| parity::logical_name(file.name.as_ref().unwrap()));
+    check_name_free(hub, target_folder, target_name, &[], duplicate).await;
+    files.append(&mut create_parity_list(hub, file).await);
+    let mut copied_files = Vec::new();
+
+    for (index, file) in files.iter().enumerate() {
+        let old_name = file.name.as_ref().unwrap();
+        let name = match (new_name, misc::part_suffix(old_name)) {
+            (Some(new_name), _) if index >= part_count => parity::renamed_parity(old_name, new_name),
+            (Some(new_name), Some(suffix)) if part_count > 1 => format!("{}{}", new_name, suffix),
+            (Some(new_name), _) => new_name.to_owned(),
+            (None, _) => old_name.to_owned(),
+        };
+
+        let req = File {
+            name: Some(name),
+            description: file.description.clone(),
+            parents: folder_id.clone().map(|id| vec![id]).or_else(|| file.parents.clone()),
+            ..Default::default()
+        };
+
+        let (_, copied_file) = hub.files()
+            .copy(req, file.id.as_ref().unwrap())
+            .supports_all_drives(true)
+            .param("fields", FIELDS)
+            .add_scope(auth::scope())
+            .doit()
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed at copying the file '{}' - {}", old_name, e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_025);
+            });
+        info!("Copied '{}' to '{}'", old_name, copied_file.name.as_ref().unwrap());
+
+        copied_files.push(copied_file);
+    }
+
+    copied_files
+}
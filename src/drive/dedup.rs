@@ -0,0 +1,453 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::crypto;
+use crate::drive::delete::delete;
+use crate::drive::find::{find, FindFilter};
+use crate::drive::info::FIELDS;
+use crate::drive::list::list;
+use crate::drive::mkdir::create_folder;
+use crate::drive::path::children;
+use crate::drive::set_trashed::set_trashed;
+use crate::drive::upload::{UploadOptions, UploadResult};
+use crate::misc;
+use chrono::{DateTime, Duration, Utc};
+use fastcdc::v2020::StreamCDC;
+use futures::stream::{self, StreamExt};
+use google_drive3::api::File;
+use google_drive3::hyper::body::{self, Bytes};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use std::process::exit;
+
+// The index of a deduplicated upload is uploaded with this MIME type, which is how `download` recognizes it
+pub const INDEX_MIME_TYPE: &str = "application/x-pipe2drive-index+json";
+// The name of the folder the chunks are stored in (in the folder of the upload), if no chunk folder is selected
+pub const DEFAULT_CHUNK_FOLDER: &str = ".chunks";
+
+// The chunk sizes of the content-defined chunking (FastCDC), the same data is always split the same way
+const MIN_CHUNK_SIZE: u32 = 1024 * 1024;
+const AVG_CHUNK_SIZE: u32 = 4 * 1024 * 1024;
+const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+
+// How many chunks there are uploaded/downloaded at the same time
+const CONCURRENT_CHUNKS: usize = 4;
+
+// The chunks modified in the last hours are never collected, they may belong to an upload there is still running
+// (the index is only uploaded after all the chunks)
+const GC_GRACE_HOURS: i64 = 24;
+
+// The index of a deduplicated upload, the chunks in the order of the data
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChunkIndex {
+    pub version: u32,
+    pub size: u64,
+    pub encrypted: bool,
+    pub chunk_folder_id: String,
+    pub chunks: Vec<ChunkRef>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ChunkRef {
+    pub hash: String,
+    // The size of the data before it is encrypted
+    pub size: u64,
+    pub id: String,
+}
+
+pub fn is_index(file: &File) -> bool {
+    file.mime_type.as_deref() == Some(INDEX_MIME_TYPE)
+}
+
+// A chunk is named by the keyed hash of the data (before it is encrypted), so the same data always has the same name
+fn chunk_name(key: &[u8; 32], data: &[u8], encrypted: bool) -> String {
+    let hash = blake3::keyed_hash(key, data).to_hex().to_string();
+    if encrypted { format!("{}.age", hash) } else { hash }
+}
+
+fn split<R: Read>(reader: R) -> StreamCDC<R> {
+    StreamCDC::new(reader, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE)
+}
+
+async fn chunk_folder(hub: &HubType, parent_folder_id: Option<String>) -> String {
+    let existing = children(hub, parent_folder_id.clone()).await
+        .into_iter()
+        .find(|file| file.is_folder() && file.name == DEFAULT_CHUNK_FOLDER);
+
+    match existing {
+        Some(folder) => folder.id.unwrap(),
+        None => create_folder(hub, DEFAULT_CHUNK_FOLDER, parent_folder_id).await.id.unwrap(),
+    }
+}
+
+async fn upload_chunk(hub: &HubType, name: String, data: Vec<u8>, folder_id: String) -> Result<File, String> {
+    let req = File {
+        name: Some(name.clone()),
+        parents: Some(vec![folder_id]),
+        ..Default::default()
+    };
+
+    hub.files()
+        .create(req)
+        .supports_all_drives(true)
+        .param("fields", FIELDS)
+        .add_scope(auth::scope())
+        .upload_resumable(Cursor::new(data), "application/octet-stream".parse().unwrap())
+        .await
+        .map(|(_, file)| {
+            debug!("Uploaded the chunk '{}'", name);
+            file
+        })
+        .map_err(|e| {
+            auth::log_scope_hint(&e);
+            format!("Failed at uploading the chunk '{}' - {}", name, e)
+        })
+}
+
+// Uploads the data split into chunks, only the chunks there are not already in the chunk folder are uploaded,
+// and an index listing the chunks is uploaded as the file. The chunk folder is by default the folder '.chunks'
+// in the folder of the upload. A failed upload leaves the uploaded chunks, they are used by the next upload.
+// The parity parts and keeping a partial upload are not supported by the options
+pub async fn upload_dedup<T: Read>(
+    hub: &HubType,
+    buffer: T,
+    filename: String,
+    parent_folder_id: Option<String>,
+    chunk_folder_id: Option<String>,
+    options: &UploadOptions,
+) -> UploadResult {
+    let mut upload_status = UploadResult {
+        complete: false,
+        uploaded_files: vec![],
+        deleted_files: vec![],
+        data_size: None,
+        parity_failed: false,
+    };
+
+    let parent_folder_id = parent_folder_id.or_else(auth::default_folder);
+
+    // Check if there already exist files with the name
+    let mut existing_files = Vec::new();
+    if !options.duplicate {
+        existing_files = misc::file_filter(
+            format!(r#"^{}(\.[0-9]+)?$"#, regex::escape(&filename)).as_str(),
+            &list(hub, parent_folder_id.clone()).await,
+        );
+        if !existing_files.is_empty() && !options.replace {
+            error!("The file '{}' already exist, use the replace flag (--replace) \
+                    if you want to replace this file, or use the duplicate flag (--duplicate) \
+                    if you don't care that multiple files have the same filename",
+                   filename);
+            exit(misc::EXIT_CODE_008);
+        }
+    }
+
+    let chunk_folder_id = match chunk_folder_id {
+        Some(id) => id,
+        None => chunk_folder(hub, parent_folder_id.clone()).await,
+    };
+
+    let key = crypto::load_dedup_key();
+    let encrypted = options.encryption_pub_key.is_some();
+    let chunk_ids: HashMap<String, String> = list(hub, Some(chunk_folder_id.clone())).await
+        .into_iter()
+        .filter_map(|file| Some((file.name?, file.id?)))
+        .collect();
+    info!("Starter uploading (deduplicated), the chunk folder contains {} chunk(s)", chunk_ids.len());
+
+    // The chunks there are new (and not already being uploaded) are uploaded in parallel, while keeping the order
+    let mut uploading: HashSet<String> = HashSet::new();
+    let mut results = stream::iter(split(buffer))
+        .map(|chunk| {
+            let chunk = chunk.map_err(|e| format!("Failed at reading the data - {}", e));
+            let upload = chunk.map(|chunk| {
+                let name = chunk_name(&key, &chunk.data, encrypted);
+                let is_new = !chunk_ids.contains_key(&name) && uploading.insert(name.clone());
+                (name, chunk.data, is_new)
+            });
+
+            let encryption_pub_key = options.encryption_pub_key.as_ref();
+            let chunk_folder_id = chunk_folder_id.clone();
+            async move {
+                let (name, data, is_new) = upload?;
+                let size = data.len() as u64;
+                if !is_new {
+                    return Ok((name, size, None));
+                }
+
+                let data = match encryption_pub_key {
+                    Some(key) => crypto::encrypt(&data, key).map_err(|e| format!("Failed at encrypting the chunk '{}' - {}", name, e))?,
+                    None => data,
+                };
+                let file = upload_chunk(hub, name.clone(), data, chunk_folder_id).await?;
+                Ok::<(String, u64, Option<File>), String>((name, size, Some(file)))
+            }
+        })
+        .buffered(CONCURRENT_CHUNKS);
+
+    let mut index = ChunkIndex {
+        version: 1,
+        size: 0,
+        encrypted,
+        chunk_folder_id: chunk_folder_id.clone(),
+        chunks: vec![],
+    };
+    let mut new_chunk_ids: HashMap<String, String> = HashMap::new();
+    let mut reused_count = 0;
+    while let Some(result) = results.next().await {
+        let (name, size, file) = match result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                error!("{}", e);
+                return upload_status;
+            }
+        };
+
+        match file {
+            Some(file) => {
+                new_chunk_ids.insert(name.clone(), file.id.clone().unwrap());
+                upload_status.uploaded_files.push(file);
+            }
+            None => reused_count += 1,
+        }
+
+        // The chunk was uploaded earlier in this upload, if it is not known yet
+        let id = match chunk_ids.get(&name).or_else(|| new_chunk_ids.get(&name)) {
+            Some(id) => id.clone(),
+            None => {
+                error!("The chunk '{}' is used twice, but was never uploaded", name);
+                return upload_status;
+            }
+        };
+        index.size += size;
+        index.chunks.push(ChunkRef {
+            hash: name.trim_end_matches(".age").to_owned(),
+            size,
+            id,
+        });
+    }
+    drop(results);
+
+    info!("Uploaded {} new chunk(s), and reused {} chunk(s) already in the chunk folder",
+          upload_status.uploaded_files.len(), reused_count);
+
+    let req = File {
+        name: Some(filename.clone()),
+        mime_type: Some(INDEX_MIME_TYPE.to_owned()),
+        parents: parent_folder_id.map(|id| vec![id]),
+        description: Some(format!("Deduplicated upload of {} bytes in {} chunk(s)", index.size, index.chunks.len())),
+        properties: if options.properties.is_empty() { None } else { Some(options.properties.clone()) },
+        ..Default::default()
+    };
+    let index_data = serde_json::to_vec(&index).unwrap();
+    let result = hub.files()
+        .create(req)
+        .supports_all_drives(true)
+        .param("fields", FIELDS)
+        .add_scope(auth::scope())
+        .upload_resumable(Cursor::new(index_data), INDEX_MIME_TYPE.parse().unwrap())
+        .await;
+
+    match result {
+        Ok((_, file)) => {
+            info!("Uploaded the index: '{}'", filename);
+            upload_status.uploaded_files.push(file);
+            upload_status.data_size = Some(index.size);
+        }
+        Err(e) => {
+            error!("Failed at uploading the index '{}' - {}", filename, e);
+            auth::log_scope_hint(&e);
+            return upload_status;
+        }
+    }
+
+    // The existing file is only deleted after the new index was uploaded
+    for file in existing_files {
+        delete(hub, &file).await;
+        upload_status.deleted_files.push(file);
+    }
+
+    upload_status.complete = true;
+    upload_status
+}
+
+async fn fetch(hub: &HubType, file_id: &str) -> Result<Bytes, String> {
+    let (response, _) = hub.files()
+        .get(file_id)
+        .supports_all_drives(true)
+        .acknowledge_abuse(false)
+        .param("alt", "media")
+        .add_scope(auth::scope())
+        .doit()
+        .await
+        .map_err(|e| {
+            auth::log_scope_hint(&e);
+            e.to_string()
+        })?;
+
+    body::to_bytes(response.into_body()).await.map_err(|e| e.to_string())
+}
+
+pub async fn load_index(hub: &HubType, file: &File) -> ChunkIndex {
+    let data = fetch(hub, file.id.as_ref().unwrap()).await.unwrap_or_else(|e| {
+        error!("Failed at downloading the index '{}' - {}", file.name.as_ref().unwrap(), e);
+        exit(misc::EXIT_CODE_028)
+    });
+
+    serde_json::from_slice(&data).unwrap_or_else(|e| {
+        error!("The index '{}' is invalid - {}", file.name.as_ref().unwrap(), e);
+        exit(misc::EXIT_CODE_028)
+    })
+}
+
+// Downloads the chunks listed in the index, and writes the data of them in order
+pub async fn restore(hub: &HubType, file: &File, stream: &mut dyn Write) {
+    let index = load_index(hub, file).await;
+    let private_key = if index.encrypted { Some(crypto::load_private_key(None)) } else { None };
+
+    info!("Starting to download the file: {} ({} chunk(s))", file.name.as_ref().unwrap(), index.chunks.len());
+    let mut chunks = stream::iter(index.chunks.iter())
+        .map(|chunk| {
+            let private_key = private_key.as_ref();
+            async move {
+                let data = fetch(hub, &chunk.id).await
+                    .map_err(|e| format!("Failed at downloading the chunk '{}' - {}", chunk.hash, e))?;
+                let data = match private_key {
+                    Some(key) => crypto::decrypt(&data, key)
+                        .map_err(|e| format!("Failed at decrypting the chunk '{}' - {}", chunk.hash, e))?,
+                    None => data.to_vec(),
+                };
+
+                if data.len() as u64 != chunk.size {
+                    return Err(format!("The chunk '{}' is {} bytes, but should be {} bytes", chunk.hash, data.len(), chunk.size));
+                }
+                Ok(data)
+            }
+        })
+        .buffered(CONCURRENT_CHUNKS);
+
+    while let Some(result) = chunks.next().await {
+        let data = result.unwrap_or_else(|e| {
+            error!("{}", e);
+            exit(misc::EXIT_CODE_028)
+        });
+        stream.write_all(&data).expect("failed to write data");
+    }
+
+    info!("Download of '{}' Completed", file.name.as_ref().unwrap());
+}
+
+// A chunk is collected if no index uses it, and it is older than the grace period
+fn is_collectable(file: &File, used: &HashSet<String>, now: DateTime<Utc>) -> bool {
+    let recent = file.modified_time.as_deref()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .is_none_or(|time| now - time.with_timezone(&Utc) < Duration::hours(GC_GRACE_HOURS));
+    !recent && !used.contains(file.id.as_ref().unwrap())
+}
+
+// Deletes (or moves to the trash) the chunks in the chunk folder, there are not listed in any index using
+// the folder. The indexes in the trash count as well, so they can still be restored. Deleting an index does
+// not delete the chunks of it, because other uploads may use the same chunks. Returns the unused chunks
+pub async fn gc(hub: &HubType, chunk_folder_id: &str, dry_run: bool, trash: bool) -> Vec<File> {
+    let mut used: HashSet<String> = HashSet::new();
+    for trashed in [false, true] {
+        let filter = FindFilter { mime_type: Some(INDEX_MIME_TYPE.to_owned()), trashed, ..Default::default() };
+        for file in find(hub, &filter).await.into_iter().flat_map(|file| file.parts) {
+            let index = load_index(hub, &file).await;
+            if index.chunk_folder_id == chunk_folder_id {
+                used.extend(index.chunks.into_iter().map(|chunk| chunk.id));
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let unused: Vec<File> = list(hub, Some(chunk_folder_id.to_owned())).await
+        .into_iter()
+        .filter(|file| is_collectable(file, &used, now))
+        .collect();
+    info!("{} chunk(s) are used, {} chunk(s) are not used by any index (and older than {} hours)",
+          used.len(), unused.len(), GC_GRACE_HOURS);
+
+    // The chunks are single files, so they are deleted one by one (without looking for parts)
+    for file in &unused {
+        let name = file.name.as_ref().unwrap();
+        if dry_run {
+            info!("Would delete the chunk '{}' (dry run)", name);
+        } else if trash {
+            set_trashed(hub, file.id.as_ref().unwrap(), true).await.unwrap_or_else(|e| {
+                error!("Failed at moving the chunk '{}' to the trash - {}", name, e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_009);
+            });
+            info!("Moved '{}' to the trash", name);
+        } else {
+            delete(hub, file).await;
+        }
+    }
+
+    unused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chunk_name, is_collectable, split};
+    use chrono::{TimeZone, Utc};
+    use google_drive3::api::File;
+    use std::collections::HashSet;
+
+    // Deterministic data without any repetitions, so the chunk boundaries depends on the content
+    fn data(size: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..size).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as u8
+        }).collect()
+    }
+
+    fn chunk_names(data: &[u8]) -> Vec<String> {
+        split(data).map(|chunk| chunk_name(&[7u8; 32], &chunk.unwrap().data, false)).collect()
+    }
+
+    #[test]
+    fn test_010_chunk_name() {
+        let key = [7u8; 32];
+        assert_eq!(chunk_name(&key, b"0123456789", false), chunk_name(&key, b"0123456789", false));
+        assert_ne!(chunk_name(&key, b"0123456789", false), chunk_name(&[8u8; 32], b"0123456789", false));
+        assert_eq!(64, chunk_name(&key, b"0123456789", false).len());
+        assert!(chunk_name(&key, b"0123456789", true).ends_with(".age"));
+    }
+
+    #[test]
+    fn test_020_insert_changes_few_chunks() {
+        let original = data(40 * 1024 * 1024, 1);
+        let mut changed = original.clone();
+        changed.splice(20 * 1024 * 1024..20 * 1024 * 1024, b"inserted in the middle".iter().copied());
+
+        let original_names = chunk_names(&original);
+        let changed_names = chunk_names(&changed);
+        assert_eq!(original.len(), split(&original[..]).map(|chunk| chunk.unwrap().length).sum::<usize>());
+
+        // Only the chunks around the insert are new, the boundaries after it are found again
+        let known: HashSet<&String> = original_names.iter().collect();
+        let new_count = changed_names.iter().filter(|name| !known.contains(name)).count();
+        assert!(original_names.len() > 4);
+        assert!(new_count <= 2, "{} of {} chunks are new", new_count, changed_names.len());
+    }
+
+    #[test]
+    fn test_030_is_collectable() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let chunk = |id: &str, modified_time: &str| File {
+            id: Some(id.to_owned()),
+            name: Some(id.to_owned()),
+            modified_time: Some(modified_time.to_owned()),
+            ..Default::default()
+        };
+        let used = HashSet::from(["used".to_owned()]);
+
+        assert!(is_collectable(&chunk("old", "2026-10-17T12:00:00.000Z"), &used, now));
+        assert!(!is_collectable(&chunk("used", "2026-10-17T12:00:00.000Z"), &used, now));
+        // The chunk may belong to an upload there is still running
+        assert!(!is_collectable(&chunk("new", "2026-10-19T11:00:00.000Z"), &used, now));
+    }
+}
@@ -1,13 +1,16 @@
+use crate::auth;
 use crate::auth::HubType;
+use crate::drive::list::{create_file_list, create_parity_list};
+use crate::drive::set_trashed::set_trashed_all;
 use crate::misc;
-use google_drive3::api::{File, Scope};
+use google_drive3::api::File;
 use std::process::exit;
 
 pub async fn delete(hub: &HubType, file: &File) {
     hub.files()
         .delete(file.id.as_ref().unwrap())
         .supports_all_drives(true)
-        .add_scope(Scope::Full)
+        .add_scope(auth::scope())
         .doit()
         .await
         .unwrap_or_else(|e| {
@@ -16,7 +19,24 @@ pub async fn delete(hub: &HubType, file: &File) {
                 file.name.as_ref().unwrap(),
                 e
             );
+            auth::log_scope_hint(&e);
             exit(misc::EXIT_CODE_009);
         });
     info!("Deleted '{}", file.name.as_ref().unwrap())
 }
+
+// Deletes (or moves to the trash) all the parts of a file, which was split into multiple files,
+// and the parity parts of it
+pub async fn delete_all(hub: &HubType, file: &File, trash: bool) -> Vec<File> {
+    if trash {
+        return set_trashed_all(hub, file, true).await;
+    }
+
+    let mut files = create_file_list(hub, file).await;
+    files.append(&mut create_parity_list(hub, file).await);
+    for file in &files {
+        delete(hub, file).await;
+    }
+
+    files
+}
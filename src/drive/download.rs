@@ -1,7 +1,9 @@
+use crate::auth;
 use crate::auth::HubType;
+use crate::drive::dedup;
 use crate::drive::list::create_file_list;
+use crate::drive::parity;
 use crate::misc;
-use google_drive3::api::Scope;
 use google_drive3::hyper::body::{HttpBody, Bytes};
 use std::borrow::BorrowMut;
 use std::io::Write;
@@ -16,7 +18,7 @@ pub async fn download<'a>(hub: &HubType, info: &File, stream: Option<&mut dyn Wr
 pub (crate) async fn download_overwrite_options<'a>(
     hub: &HubType,
     info: &File,
-    mut stream: Option<&mut dyn Write>,
+    stream: Option<&mut dyn Write>,
     overwrite_zero_count: Option<i64>,
 ) {
     // If the file is trashed, don't download
@@ -31,6 +33,35 @@ pub (crate) async fn download_overwrite_options<'a>(
     // Get all the sibling to this file, if it has any
     let files = create_file_list(hub, info).await;
 
+    // Missing and damaged parts are rebuilt, if the file was uploaded with parity parts
+    if overwrite_zero_count.is_none() {
+        if let Some(repair) = parity::check(hub, info, &files).await {
+            return parity::restore(hub, repair, stream).await;
+        }
+    }
+
+    download_parts(hub, files, stream, overwrite_zero_count).await
+}
+
+// Downloads the parts of a file (sorted by name) as one file, without the filler bytes (0x00) at the end.
+// A deduplicated upload (the index) is reassembled from the chunks instead
+pub(crate) async fn download_parts(
+    hub: &HubType,
+    files: Vec<File>,
+    mut stream: Option<&mut dyn Write>,
+    overwrite_zero_count: Option<i64>,
+) {
+    if let [index] = files.as_slice() {
+        if dedup::is_index(index) {
+            let mut file;
+            if stream.is_none() {
+                file = ::std::fs::File::create(index.name.as_ref().unwrap()).expect("Unable to open file");
+                stream = Some(file.borrow_mut());
+            }
+            return dedup::restore(hub, index, stream.unwrap()).await;
+        }
+    }
+
     // Figure out what the filename is
     let file_name: String;
     if files.len() == 1 {
@@ -74,7 +105,7 @@ pub (crate) async fn download_overwrite_options<'a>(
 
     // Figure out, how much of the last file can be skipped, because of it just being fill'er bytes (0x00)
     let zero_count = overwrite_zero_count.unwrap_or(
-        files.last().unwrap().description.as_ref().map_or(0, |s| s.trim().parse::<i64>().unwrap_or(0))
+        misc::parse_padding(&files.last().unwrap().description)
     );
 
     // Some validation of the value found in the description
@@ -113,11 +144,12 @@ pub (crate) async fn download_overwrite_options<'a>(
             .supports_all_drives(true)
             .acknowledge_abuse(false)
             .param("alt", "media")
-            .add_scope(Scope::Full)
+            .add_scope(auth::scope())
             .doit()
             .await
             .unwrap_or_else(|e| {
                 error!("{}", e);
+                auth::log_scope_hint(&e);
                 exit(misc::EXIT_CODE_010);
             });
 
@@ -0,0 +1,84 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::misc;
+use google_drive3::api::Drive;
+use std::process::exit;
+
+pub const DRIVE_FIELDS: &str = "id,name,createdTime,hidden";
+
+pub async fn list_drives(hub: &HubType) -> Vec<Drive> {
+    let mut drives: Vec<Drive> = Vec::new();
+
+    info!("Loading the list of shared drives");
+
+    let mut next_page_token: Option<String> = None;
+    while {
+        let mut build = hub.drives().list()
+            .page_size(100)
+            .param("fields", format!("nextPageToken,drives({})", DRIVE_FIELDS).as_str());
+
+        if let Some(page_token) = next_page_token.as_deref() {
+            build = build.page_token(page_token)
+        }
+
+        let (_, drive_list) = build
+            .add_scope(auth::scope())
+            .doit()
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed at listing the shared drives - {}", e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_023)
+            });
+
+        next_page_token = drive_list.next_page_token;
+        let mut tmp = drive_list.drives.unwrap_or_default();
+        drives.append(tmp.as_mut());
+
+        next_page_token.is_some()
+    } {}
+
+    drives
+}
+
+// Finds the shared drive by ID or by name, if multiple shared drives have the name, the IDs of them are listed
+pub async fn resolve_drive(hub: &HubType, name_or_id: &str) -> Drive {
+    let mut matches: Vec<Drive> = list_drives(hub).await
+        .into_iter()
+        .filter(|drive| drive.id.as_deref() == Some(name_or_id) || drive.name.as_deref() == Some(name_or_id))
+        .collect();
+
+    if let Some(index) = matches.iter().position(|drive| drive.id.as_deref() == Some(name_or_id)) {
+        return matches.remove(index);
+    }
+
+    match matches.len() {
+        0 => {
+            error!("The shared drive '{}' does not exist (or you do not have access to it)", name_or_id);
+            exit(misc::EXIT_CODE_023)
+        }
+        1 => matches.remove(0),
+        _ => {
+            error!("The shared drive name '{}' is ambiguous, use one of the IDs instead: {}",
+                   name_or_id,
+                   matches.iter().map(|drive| drive.id.clone().unwrap_or_default()).collect::<Vec<String>>().join(", "));
+            exit(misc::EXIT_CODE_023)
+        }
+    }
+}
+
+// Selects the shared drive used instead of 'My Drive' for the rest of the runtime
+pub async fn select_drive(hub: &HubType, name_or_id: Option<String>) {
+    let Some(name_or_id) = name_or_id else {
+        return;
+    };
+
+    if auth::access_scope() == auth::AccessScope::Appdata {
+        error!("A shared drive cannot be used with the scope 'appdata'");
+        exit(misc::EXIT_CODE_023)
+    }
+
+    let drive = resolve_drive(hub, &name_or_id).await;
+    debug!("Using the shared drive '{}' ({})", drive.name.as_deref().unwrap_or_default(), drive.id.as_ref().unwrap());
+    auth::init_shared_drive(drive.id.unwrap());
+}
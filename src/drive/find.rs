@@ -0,0 +1,244 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::info::FIELDS;
+use crate::drive::list::{group_files, LogicalFile, FOLDER_MIME_TYPE};
+use crate::misc;
+use chrono::{DateTime, Utc};
+use google_drive3::api::File;
+use regex::Regex;
+use std::process::exit;
+
+// The filters of `find`, the ones there are valid for every part of a split file are sent to
+// Google Drive as a query (`q`), the rest is matched against the logical (grouped) files
+#[derive(Default, Debug)]
+pub struct FindFilter {
+    pub folder_id: Option<String>,
+    pub name_glob: Option<String>,
+    pub modified_before: Option<DateTime<Utc>>,
+    pub larger_than: Option<u64>,
+    pub mime_type: Option<String>,
+    pub trashed: bool,
+    pub encrypted: bool,
+    // The tags (KEY=VALUE) are saved as properties of the files
+    pub tags: Vec<(String, String)>,
+}
+
+impl FindFilter {
+    pub fn query(&self) -> String {
+        let mut clauses = vec![format!("trashed = {}", self.trashed)];
+
+        if let Some(folder_id) = self.folder_id.as_ref() {
+            clauses.push(format!("'{}' in parents", escape(folder_id)));
+        }
+
+        // Google Drive only supports prefix matching of names, so the part of the glob before
+        // the first wildcard is used to narrow down the result, the glob itself is matched later
+        if let Some(prefix) = self.name_glob.as_deref().map(glob_prefix).filter(|prefix| !prefix.is_empty()) {
+            clauses.push(format!("name contains '{}'", escape(prefix)));
+        }
+
+        match self.mime_type.as_deref() {
+            Some(mime_type) if mime_type.ends_with("/*") => {
+                clauses.push(format!("mimeType contains '{}'", escape(&mime_type[..mime_type.len() - 1])))
+            }
+            Some(mime_type) => clauses.push(format!("mimeType = '{}'", escape(mime_type))),
+            None => {}
+        }
+
+        for (key, value) in self.tags.iter() {
+            clauses.push(format!("properties has {{ key='{}' and value='{}' }}", escape(key), escape(value)));
+        }
+
+        clauses.join(" and ")
+    }
+
+    pub fn matches(&self, file: &LogicalFile) -> bool {
+        if let Some(glob) = self.name_glob.as_deref() {
+            if !glob_to_regex(glob).is_match(&file.name) {
+                return false;
+            }
+        }
+
+        if let Some(modified_before) = self.modified_before.as_ref() {
+            let modified_time = file.modified_time.as_deref()
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok());
+            match modified_time {
+                Some(modified_time) if modified_time < *modified_before => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(larger_than) = self.larger_than {
+            match file.size {
+                Some(size) if size > larger_than as i64 => {}
+                _ => return false,
+            }
+        }
+
+        !self.encrypted || file.encrypted
+    }
+}
+
+// True if the file has all the tags (as properties)
+pub fn has_tags(file: &File, tags: &[(String, String)]) -> bool {
+    tags.iter().all(|(key, value)| {
+        file.properties.as_ref().and_then(|properties| properties.get(key)) == Some(value)
+    })
+}
+
+// `folder` is accepted as a short form of the folder MIME type
+pub fn parse_mime_type(mime_type: &str) -> String {
+    match mime_type {
+        "folder" => FOLDER_MIME_TYPE.to_owned(),
+        mime_type => mime_type.to_owned(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn glob_prefix(glob: &str) -> &str {
+    &glob[..glob.find(['*', '?']).unwrap_or(glob.len())]
+}
+
+// Supports the wildcards '*' (any number of characters) and '?' (exactly one character)
+pub(crate) fn glob_to_regex(glob: &str) -> Regex {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    Regex::new(&regex).expect("Something is wrong with your regular expression")
+}
+
+pub async fn find(hub: &HubType, filter: &FindFilter) -> Vec<LogicalFile> {
+    let mut files: Vec<File> = Vec::new();
+    let query = filter.query();
+
+    info!("Searching for files");
+    debug!("Query: {}", query);
+
+    let mut next_page_token: Option<String> = None;
+    while {
+        let mut build = hub.files().list()
+            .param("fields", format!("nextPageToken,files({})", FIELDS).as_str())
+            .q(query.as_str());
+
+        if auth::access_scope() == auth::AccessScope::Appdata {
+            build = build.spaces(auth::APP_DATA_FOLDER)
+        } else if let Some(drive_id) = auth::shared_drive() {
+            build = build
+                .corpora("drive")
+                .drive_id(drive_id)
+                .include_items_from_all_drives(true)
+                .supports_all_drives(true)
+        }
+        if let Some(page_token) = next_page_token.as_deref() {
+            build = build.page_token(page_token)
+        }
+
+        let (_, file_list) = build
+            .add_scope(auth::scope())
+            .doit()
+            .await
+            .unwrap_or_else(|e| {
+                error!("Search request failed - {}", e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_007)
+            });
+
+        next_page_token = file_list.next_page_token;
+        let mut tmp = file_list.files.unwrap_or_default();
+        files.append(tmp.as_mut());
+
+        next_page_token.is_some()
+    } {}
+
+    group_files(files)
+        .into_iter()
+        .filter(|file| filter.matches(file))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_to_regex, has_tags, FindFilter};
+    use crate::drive::list::group_files;
+    use google_drive3::api::File;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_010_query() {
+        let filter = FindFilter {
+            folder_id: Some("abc".to_owned()),
+            name_glob: Some("db's-*.tar".to_owned()),
+            mime_type: Some("application/*".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            r"trashed = false and 'abc' in parents and name contains 'db\'s-' and mimeType contains 'application/'",
+            filter.query()
+        );
+
+        let filter = FindFilter { trashed: true, name_glob: Some("*.age".to_owned()), ..Default::default() };
+        assert_eq!("trashed = true", filter.query());
+
+        let filter = FindFilter { tags: vec![("host".to_owned(), "web1".to_owned())], ..Default::default() };
+        assert_eq!("trashed = false and properties has { key='host' and value='web1' }", filter.query());
+    }
+
+    #[test]
+    fn test_020_glob() {
+        assert!(glob_to_regex("backup-*.tar").is_match("backup-2026-10-18.tar"));
+        assert!(glob_to_regex("db.?").is_match("db.1"));
+        assert!(!glob_to_regex("db.?").is_match("db.10"));
+        assert!(!glob_to_regex("*.tar").is_match("backup.tar.gz"));
+    }
+
+    #[test]
+    fn test_030_matches_logical_files() {
+        let part = |id: &str, name: &str, size: &str, time: &str| File {
+            id: Some(id.to_owned()),
+            name: Some(name.to_owned()),
+            mime_type: Some("application/octet-stream".to_owned()),
+            size: Some(size.to_owned()),
+            modified_time: Some(time.to_owned()),
+            ..Default::default()
+        };
+        let files = group_files(vec![
+            part("1", "backup.tar.age.000", "100", "2026-10-01T10:00:00.000Z"),
+            part("2", "backup.tar.age.001", "100", "2026-10-03T10:00:00.000Z"),
+        ]);
+
+        let filter = FindFilter {
+            name_glob: Some("backup.*".to_owned()),
+            larger_than: Some(150),
+            encrypted: true,
+            modified_before: Some("2026-10-04T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&files[0]));
+
+        // The newest part decides the modified time of the file
+        let filter = FindFilter { modified_before: Some("2026-10-02T00:00:00Z".parse().unwrap()), ..Default::default() };
+        assert!(!filter.matches(&files[0]));
+    }
+
+    #[test]
+    fn test_040_has_tags() {
+        let file = File {
+            properties: Some(HashMap::from([("host".to_owned(), "web1".to_owned()), ("env".to_owned(), "prod".to_owned())])),
+            ..Default::default()
+        };
+        assert!(has_tags(&file, &[("env".to_owned(), "prod".to_owned())]));
+        assert!(!has_tags(&file, &[("env".to_owned(), "test".to_owned())]));
+        assert!(!has_tags(&File::default(), &[("env".to_owned(), "prod".to_owned())]));
+        assert!(has_tags(&File::default(), &[]));
+    }
+}
@@ -1,20 +1,22 @@
+use crate::auth;
 use crate::auth::HubType;
 use crate::misc;
-use google_drive3::api::{File, Scope};
+use google_drive3::api::File;
 use std::process::exit;
 
-pub const FIELDS: &str = "mimeType,id,kind,teamDriveId,name,driveId,description,size,md5Checksum,parents,trashed";
+pub const FIELDS: &str = "mimeType,id,kind,teamDriveId,name,driveId,description,size,md5Checksum,parents,trashed,modifiedTime,properties,appProperties";
 
 pub async fn info(hub: &HubType, id: &str) -> File {
     let (_, file) = hub.files().get(id)
         .supports_all_drives(true)
         .acknowledge_abuse(false)
         .param("fields", FIELDS)
-        .add_scope(Scope::Full)
+        .add_scope(auth::scope())
         .doit()
         .await
         .unwrap_or_else(|e| {
             error!("{}", e);
+            auth::log_scope_hint(&e);
             exit(misc::EXIT_CODE_010);
         });
 
@@ -1,30 +1,165 @@
+use crate::auth;
 use crate::auth::HubType;
+use crate::drive::info::FIELDS;
+use crate::drive::parity;
 use crate::misc;
 use crate::misc::file_filter;
-use google_drive3::api::{File, Scope};
+use google_drive3::api::File;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::process::exit;
 
+pub const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+// A file as it was uploaded, which may be split into multiple files (parts)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogicalFile {
+    pub id: Option<String>,
+    pub name: String,
+    pub mime_type: Option<String>,
+    // The total size of all the parts minus the filler bytes (0x00)
+    pub size: Option<i64>,
+    pub part_count: usize,
+    pub encrypted: bool,
+    pub modified_time: Option<String>,
+    pub parts: Vec<File>,
+    // The parity parts (FILE_NAME.parity.000, ...) of the file, there are not part of the data
+    pub parity: Vec<File>,
+}
+
+impl LogicalFile {
+    fn new(mut parts: Vec<File>, name: String) -> LogicalFile {
+        parts.sort_by(|f1, f2| f1.name.cmp(&f2.name));
+
+        let size = parts.iter()
+            .map(|file| file.size.as_ref().and_then(|s| s.parse::<i64>().ok()))
+            .sum::<Option<i64>>()
+            .map(|size| size - misc::parse_padding(&parts.last().unwrap().description));
+
+        LogicalFile {
+            id: parts.first().unwrap().id.clone(),
+            mime_type: parts.first().unwrap().mime_type.clone(),
+            size,
+            part_count: parts.len(),
+            encrypted: name.ends_with(".age"),
+            modified_time: parts.iter().filter_map(|file| file.modified_time.clone()).max(),
+            name,
+            parts,
+            parity: Vec::new(),
+        }
+    }
+
+    pub fn is_folder(&self) -> bool {
+        self.mime_type.as_deref() == Some(FOLDER_MIME_TYPE)
+    }
+}
+
+// Groups the parts of files split into multiple files (FILE_NAME.000, FILE_NAME.001, ...)
+// into one logical file each, matching the files the same way as `create_file_list`.
+// Only parts in the same folder are grouped (the files may come from multiple folders, e.g. with `find`)
+pub fn group_files(files: Vec<File>) -> Vec<LogicalFile> {
+    let is_part = |file: &File| file.mime_type.as_deref() != Some(FOLDER_MIME_TYPE)
+        && misc::part_suffix(file.name.as_ref().unwrap()).is_some();
+    let parent = |file: &File| file.parents.as_ref().and_then(|parents| parents.first().cloned()).unwrap_or_default();
+
+    let stems: HashSet<(String, String)> = files.iter()
+        .filter(|file| is_part(file))
+        .map(|file| {
+            let name = file.name.as_ref().unwrap();
+            (parent(file), name[..name.len() - misc::part_suffix(name).unwrap().len()].to_owned())
+        })
+        .collect();
+
+    // The key is the folder, the logical name and (for files there is never grouped) the ID
+    let mut groups: Vec<(String, Vec<File>)> = Vec::new();
+    let mut group_index: HashMap<(String, String, Option<String>), usize> = HashMap::new();
+    for file in files {
+        let name = file.name.as_ref().unwrap();
+        let (key, logical_name) = if is_part(&file) {
+            let stem = name[..name.len() - misc::part_suffix(name).unwrap().len()].to_owned();
+            ((parent(&file), stem.clone(), None), stem)
+        } else if file.mime_type.as_deref() != Some(FOLDER_MIME_TYPE) && stems.contains(&(parent(&file), name.to_owned())) {
+            ((parent(&file), name.to_owned(), None), name.to_owned())
+        } else {
+            // Files there are not split, are never grouped (even if they have the same name)
+            ((parent(&file), name.to_owned(), file.id.clone()), name.to_owned())
+        };
+
+        match group_index.get(&key) {
+            Some(index) => groups[*index].1.push(file),
+            None => {
+                group_index.insert(key, groups.len());
+                groups.push((logical_name, vec![file]));
+            }
+        }
+    }
+
+    let mut files: Vec<LogicalFile> = groups.into_iter()
+        .map(|(name, parts)| LogicalFile::new(parts, name))
+        .collect();
+
+    // The parity parts are attached to the file in the same folder there belong to, instead of being a file of
+    // their own (FILE_NAME.parity). Parity parts without the file are kept, so they can be found and deleted
+    let data_files: HashMap<(String, String), usize> = files.iter()
+        .enumerate()
+        .filter(|(_, file)| !file.is_folder())
+        .map(|(index, file)| ((parent(&file.parts[0]), file.name.clone()), index))
+        .collect();
+    let mut attached: HashSet<usize> = HashSet::new();
+    for index in 0..files.len() {
+        let is_parity = files[index].parts[0].name.as_deref() != Some(files[index].name.as_str());
+        let data_name = match files[index].name.strip_suffix(".parity") {
+            Some(data_name) if is_parity => data_name.to_owned(),
+            _ => continue,
+        };
+        if let Some(data_index) = data_files.get(&(parent(&files[index].parts[0]), data_name)) {
+            files[*data_index].parity = std::mem::take(&mut files[index].parts);
+            attached.insert(index);
+        }
+    }
+
+    files.into_iter()
+        .enumerate()
+        .filter(|(index, _)| !attached.contains(index))
+        .map(|(_, file)| file)
+        .collect()
+}
+
 pub async fn list(hub: &HubType, parent_folder_id: Option<String>) -> Vec<File> {
+    list_files(hub, parent_folder_id, false).await
+}
+
+// Lists the trashed files in the folder as well
+pub(crate) async fn list_with_trashed(hub: &HubType, parent_folder_id: Option<String>) -> Vec<File> {
+    list_files(hub, parent_folder_id, true).await
+}
+
+// With `include_trashed` the trashed files in the folder are listed as well (needed to find the parts of a trashed file)
+async fn list_files(hub: &HubType, parent_folder_id: Option<String>, include_trashed: bool) -> Vec<File> {
     let mut files: Vec<File> = Vec::new();
+    let parent_folder_id = parent_folder_id.or_else(auth::default_folder);
 
     info!("Loading file list");
 
     let mut next_page_token: Option<String> = None;
     while {
         let mut build = hub.files().list()
-            .param("fields", "files(mimeType,id,kind,teamDriveId,name,driveId,description,size,md5Checksum,parents,trashed)");
+            .param("fields", format!("nextPageToken,files({})", FIELDS).as_str());
 
-        if parent_folder_id.is_some() {
+        if parent_folder_id.as_deref() == Some(auth::APP_DATA_FOLDER) {
+            build = build
+                .spaces(auth::APP_DATA_FOLDER)
+                .q(parents_query(auth::APP_DATA_FOLDER, include_trashed).as_str())
+        } else if parent_folder_id.is_some() {
+            build = match auth::shared_drive() {
+                Some(drive_id) => build.corpora("drive").drive_id(drive_id),
+                None => build.corpora("allDrives"),
+            };
             build = build
-                .corpora("allDrives")
                 .include_items_from_all_drives(true)
                 .supports_all_drives(true)
-                .q(format!(
-                    "'{}' in parents and trashed = false",
-                    parent_folder_id.as_ref().clone().unwrap()
-                )
-                .as_str())
+                .q(parents_query(parent_folder_id.as_ref().unwrap(), include_trashed).as_str())
         } else {
             build = build.q("'root' in parents")
         }
@@ -33,11 +168,12 @@ pub async fn list(hub: &HubType, parent_folder_id: Option<String>) -> Vec<File>
         }
 
         let (_, file_list) = build
-            .add_scope(Scope::Full)
+            .add_scope(auth::scope())
             .doit()
             .await
             .unwrap_or_else(|e| {
                 error!("List request failed - {}", e);
+                auth::log_scope_hint(&e);
                 exit(misc::EXIT_CODE_007)
             });
 
@@ -55,7 +191,27 @@ pub async fn list(hub: &HubType, parent_folder_id: Option<String>) -> Vec<File>
     files
 }
 
+fn parents_query(parent_folder_id: &str, include_trashed: bool) -> String {
+    if include_trashed {
+        format!("'{}' in parents", parent_folder_id)
+    } else {
+        format!("'{}' in parents and trashed = false", parent_folder_id)
+    }
+}
+
+// Finds all the parts of the file, if the file is trashed, only the trashed parts are found
 pub async fn create_file_list(hub: &HubType, file: &File) -> Vec<File> {
+    find_parts(hub, file, false).await
+}
+
+// Finds all the parts of the file, trashed or not (e.g. to report the trashed parts of a file)
+pub(crate) async fn create_file_list_with_trashed(hub: &HubType, file: &File) -> Vec<File> {
+    find_parts(hub, file, true).await
+}
+
+async fn find_parts(hub: &HubType, file: &File, include_trashed: bool) -> Vec<File> {
+    let trashed = file.trashed.unwrap_or(false);
+    let filter_trashed = |files: Vec<File>| if include_trashed { files } else { same_trashed(files, trashed) };
     let mut files: Vec<File> = Vec::new();
 
     let tmp_path = file.name.as_ref().unwrap().parse::<PathBuf>().unwrap();
@@ -72,7 +228,7 @@ pub async fn create_file_list(hub: &HubType, file: &File) -> Vec<File> {
                             regex::escape(tmp_path.file_stem().unwrap().to_str().unwrap())
                         )
                         .as_str(),
-                        &list(hub, Some(p.to_owned())).await,
+                        &filter_trashed(list_files(hub, Some(p.to_owned()), trashed || include_trashed).await),
                     );
                     files.sort_by(|f1, f2| f1.name.as_ref().unwrap().cmp(f2.name.as_ref().unwrap()));
                 }
@@ -83,7 +239,7 @@ pub async fn create_file_list(hub: &HubType, file: &File) -> Vec<File> {
                         regex::escape(tmp_path.file_stem().unwrap().to_str().unwrap())
                     )
                     .as_str(),
-                    &list(hub, None).await,
+                    &filter_trashed(list_files(hub, None, trashed || include_trashed).await),
                 );
                 files.sort_by(|f1, f2| f1.name.as_ref().unwrap().cmp(f2.name.as_ref().unwrap()));
             }
@@ -96,3 +252,137 @@ pub async fn create_file_list(hub: &HubType, file: &File) -> Vec<File> {
 
     files
 }
+
+// Stops if the folder already has a file with the NAME (or the parts of it), other than the FILES themselves,
+// like the upload does without the duplicate flag (--duplicate)
+pub(crate) async fn check_name_free(hub: &HubType, folder_id: Option<String>, name: &str, files: &[File], duplicate: bool) {
+    if duplicate {
+        return;
+    }
+
+    let existing = file_filter(
+        format!(r#"^{}(\.[0-9]+)?$"#, regex::escape(name)).as_str(),
+        &list(hub, folder_id).await,
+    );
+    if existing.iter().any(|existing| files.iter().all(|file| file.id != existing.id)) {
+        error!("The file '{}' already exist in the folder, use the duplicate flag (--duplicate) \
+                if you don't care that multiple files have the same filename",
+               name);
+        exit(misc::EXIT_CODE_008);
+    }
+}
+
+// Finds the parity parts (FILE_NAME.parity.000, ...) of the file in the folder of it, if the file is trashed,
+// only the trashed parity parts are found
+pub(crate) async fn create_parity_list(hub: &HubType, file: &File) -> Vec<File> {
+    let trashed = file.trashed.unwrap_or(false);
+    let name = parity::logical_name(file.name.as_ref().unwrap());
+    let parent = file.parents.as_ref().and_then(|parents| parents.first().cloned());
+
+    let mut files = file_filter(&parity::parity_regex(name), &same_trashed(list_files(hub, parent, trashed).await, trashed));
+    files.sort_by(|f1, f2| f1.name.cmp(&f2.name));
+    files
+}
+
+fn same_trashed(files: Vec<File>, trashed: bool) -> Vec<File> {
+    files.into_iter()
+        .filter(|file| file.trashed.unwrap_or(false) == trashed)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group_files, FOLDER_MIME_TYPE};
+    use google_drive3::api::File;
+
+    fn file(id: &str, name: &str, size: Option<&str>, description: Option<&str>) -> File {
+        File {
+            id: Some(id.to_owned()),
+            name: Some(name.to_owned()),
+            mime_type: Some(if size.is_some() { "application/octet-stream" } else { FOLDER_MIME_TYPE }.to_owned()),
+            size: size.map(|s| s.to_owned()),
+            description: description.map(|s| s.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_010_group_split_files() {
+        let groups = group_files(vec![
+            file("2", "backup.tar.001", Some("100"), Some("30")),
+            file("1", "backup.tar.000", Some("100"), None),
+            file("3", "notes.txt", Some("10"), None),
+            file("4", "backups", None, None),
+            file("5", "secret.age.000", Some("100"), None),
+            file("6", "secret.age.001", Some("100"), Some("0\nnightly")),
+        ]);
+
+        assert_eq!(4, groups.len());
+
+        assert_eq!("backup.tar", groups[0].name);
+        assert_eq!(Some("1".to_owned()), groups[0].id);
+        assert_eq!(2, groups[0].part_count);
+        assert_eq!(Some(170), groups[0].size);
+        assert!(!groups[0].encrypted);
+
+        assert_eq!("notes.txt", groups[1].name);
+        assert_eq!(1, groups[1].part_count);
+        assert_eq!(Some(10), groups[1].size);
+
+        assert_eq!("backups", groups[2].name);
+        assert!(groups[2].is_folder());
+        assert_eq!(None, groups[2].size);
+
+        assert_eq!("secret.age", groups[3].name);
+        assert_eq!(Some(200), groups[3].size);
+        assert!(groups[3].encrypted);
+    }
+
+    #[test]
+    fn test_020_do_not_group_duplicates() {
+        let groups = group_files(vec![
+            file("1", "notes.txt", Some("10"), None),
+            file("2", "notes.txt", Some("20"), None),
+        ]);
+
+        assert_eq!(2, groups.len());
+    }
+
+    #[test]
+    fn test_030_do_not_group_parts_in_different_folders() {
+        let in_folder = |id: &str, name: &str, parent: &str| File {
+            parents: Some(vec![parent.to_owned()]),
+            ..file(id, name, Some("100"), None)
+        };
+        let groups = group_files(vec![
+            in_folder("1", "db.tar.000", "host1"),
+            in_folder("2", "db.tar.000", "host2"),
+            in_folder("3", "db.tar.001", "host2"),
+        ]);
+
+        assert_eq!(2, groups.len());
+        assert_eq!(1, groups[0].part_count);
+        assert_eq!(Some(100), groups[0].size);
+        assert_eq!(2, groups[1].part_count);
+        assert_eq!(Some("2".to_owned()), groups[1].id);
+    }
+
+    #[test]
+    fn test_040_attach_parity_parts() {
+        let groups = group_files(vec![
+            file("1", "db.tar.000", Some("100"), None),
+            file("2", "db.tar.001", Some("100"), Some("30")),
+            file("3", "db.tar.parity.000", Some("100"), None),
+            file("4", "db.tar.parity.001", Some("100"), None),
+            file("5", "old.tar.parity.000", Some("100"), None),
+        ]);
+
+        assert_eq!(2, groups.len());
+        assert_eq!("db.tar", groups[0].name);
+        assert_eq!(Some(170), groups[0].size);
+        assert_eq!(2, groups[0].parity.len());
+        // The file of the parity part is gone, so the parity part is listed on its own
+        assert_eq!("old.tar.parity", groups[1].name);
+        assert!(groups[1].parity.is_empty());
+    }
+}
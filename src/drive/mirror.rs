@@ -0,0 +1,515 @@
+use crate::auth::HubType;
+use crate::crypto;
+use crate::drive::delete::delete_all;
+use crate::drive::download::download_parts;
+use crate::drive::find::glob_to_regex;
+use crate::drive::list::LogicalFile;
+use crate::drive::mkdir::create_folder;
+use crate::drive::path::{tree, TreeEntry};
+use crate::drive::upload::{upload, UploadOptions, UploadResult};
+use crate::misc;
+use age::x25519::Recipient;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use google_drive3::api::File;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::iter::once;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+// The include/exclude globs of a mirror, they are matched against the relative path and the name of
+// a file or folder, e.g. '*.log' or 'target'. An excluded folder excludes everything below it,
+// while the include globs only applies to files (without any, all the files are included)
+#[derive(Default)]
+pub struct MirrorFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl MirrorFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> MirrorFilter {
+        MirrorFilter {
+            include: include.iter().map(|glob| glob_to_regex(glob)).collect(),
+            exclude: exclude.iter().map(|glob| glob_to_regex(glob)).collect(),
+        }
+    }
+
+    pub fn matches(&self, path: &str, is_folder: bool) -> bool {
+        let is_match = |glob: &Regex, path: &str| glob.is_match(path) || glob.is_match(path.rsplit('/').next().unwrap());
+
+        let excluded = path.match_indices('/')
+            .map(|(index, _)| &path[..index])
+            .chain(once(path))
+            .any(|path| self.exclude.iter().any(|glob| is_match(glob, path)));
+        if excluded {
+            return false;
+        }
+
+        is_folder || self.include.is_empty() || self.include.iter().any(|glob| is_match(glob, path))
+    }
+}
+
+pub struct PushOptions {
+    // Files bigger than the part size are split into multiple parts, otherwise every file is a single part
+    pub part_size: Option<u64>,
+    pub encryption_pub_key: Option<Recipient>,
+    pub filter: MirrorFilter,
+    // How many files there are uploaded/downloaded at the same time
+    pub jobs: usize,
+    // Delete the files and folders in Google Drive there does not exist locally (or move them to the trash)
+    pub delete: bool,
+    pub trash: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncAction {
+    Mkdir,
+    Upload,
+    Update,
+    Delete,
+}
+
+impl SyncAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncAction::Mkdir => "mkdir",
+            SyncAction::Upload => "upload",
+            SyncAction::Update => "update",
+            SyncAction::Delete => "delete",
+        }
+    }
+}
+
+// An action planned by a push, the path is relative to the folder in Google Drive,
+// and the ID is of the existing file or folder there is updated or deleted
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlannedAction {
+    pub action: SyncAction,
+    pub path: String,
+    pub size: Option<u64>,
+    pub id: Option<String>,
+    #[serde(skip)]
+    local_path: Option<PathBuf>,
+    #[serde(skip)]
+    file: Option<File>,
+    // The parts (and parity parts) of the existing file there is updated
+    #[serde(skip)]
+    existing_files: Vec<File>,
+}
+
+struct Plan {
+    actions: Vec<PlannedAction>,
+    // The IDs of the folders there already exists in Google Drive by the path
+    folders: HashMap<String, Option<String>>,
+}
+
+// A file or folder found by walking the local folder, the path is relative to it (separated by '/')
+struct LocalEntry {
+    path: String,
+    local_path: PathBuf,
+    // `None` for a folder
+    size: Option<u64>,
+    modified_time: Option<DateTime<Utc>>,
+}
+
+impl LocalEntry {
+    // The file is unchanged, if the MD5 checksum is the same, which can only be compared if the file was uploaded as a
+    // single part without any filler bytes (0x00) and without encryption, otherwise if the size is the same (not
+    // known for an encrypted file) and it was modified in Google Drive after the local file was modified
+    fn is_unchanged(&self, remote: &LogicalFile, encrypted: bool) -> bool {
+        if !encrypted && remote.size != self.size.map(|size| size as i64) {
+            return false;
+        }
+
+        let part_size = remote.parts[0].size.as_deref().and_then(|size| size.parse::<i64>().ok());
+        if !encrypted && remote.part_count == 1 && part_size == remote.size {
+            if let Some(md5_checksum) = remote.parts[0].md5_checksum.as_deref() {
+                return file_md5(&self.local_path).is_ok_and(|md5| md5 == md5_checksum);
+            }
+        }
+
+        let remote_modified_time = remote.modified_time.as_deref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok());
+        match (remote_modified_time, self.modified_time) {
+            (Some(remote_modified_time), Some(modified_time)) => remote_modified_time >= modified_time,
+            _ => false,
+        }
+    }
+}
+
+fn file_md5(path: &Path) -> io::Result<String> {
+    let mut context = md5::Context::new();
+    io::copy(&mut fs::File::open(path)?, &mut context)?;
+    Ok(format!("{:x}", context.finalize()))
+}
+
+// Walks the local folder, depth first in alphabetical order (a folder is listed before the files in it)
+fn walk(local_dir: &Path, filter: &MirrorFilter) -> io::Result<Vec<LocalEntry>> {
+    let mut entries = Vec::new();
+    walk_folder(local_dir, "", filter, &mut entries)?;
+    Ok(entries)
+}
+
+fn walk_folder(folder: &Path, prefix: &str, filter: &MirrorFilter, entries: &mut Vec<LocalEntry>) -> io::Result<()> {
+    let mut dir_entries = fs::read_dir(folder)?.collect::<io::Result<Vec<fs::DirEntry>>>()?;
+    dir_entries.sort_by_key(|entry| entry.file_name());
+
+    for dir_entry in dir_entries {
+        let path = format!("{}{}", prefix, dir_entry.file_name().to_string_lossy());
+        let metadata = fs::metadata(dir_entry.path())?;
+        let modified_time = metadata.modified().ok().map(DateTime::<Utc>::from);
+
+        if metadata.is_dir() {
+            if !filter.matches(&path, true) {
+                continue;
+            }
+            entries.push(LocalEntry { path: path.clone(), local_path: dir_entry.path(), size: None, modified_time });
+            walk_folder(&dir_entry.path(), &format!("{}/", path), filter, entries)?;
+        } else if metadata.is_file() {
+            if !filter.matches(&path, false) {
+                continue;
+            }
+            entries.push(LocalEntry { path, local_path: dir_entry.path(), size: Some(metadata.len()), modified_time });
+        } else {
+            debug!("Skipping '{}', because it is neither a file or a folder", path);
+        }
+    }
+
+    Ok(())
+}
+
+// The size of the parts a file is uploaded with, a file (or the encrypted data of it) smaller than
+// the part size is uploaded as a single part, and an empty file as a single filler byte (0x00)
+fn part_size(size: u64, max_part_size: Option<u64>, encrypted: bool) -> u64 {
+    let data_size = if encrypted { crypto::encrypted_size(size) } else { size }.max(1);
+    max_part_size.map_or(data_size, |max_part_size| data_size.min(max_part_size))
+}
+
+// A name in Google Drive can be anything, but locally it must not leave the folder it is pulled into
+// (e.g. '..', or a name with a '/' there is an absolute path)
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+fn parent_path(path: &str) -> &str {
+    path.rfind('/').map_or("", |index| &path[..index])
+}
+
+// Compares the local folder with the folder (`None` is 'My Drive') in Google Drive, only the files there
+// are new or changed are uploaded, and with `delete` the files missing locally are deleted
+async fn plan(hub: &HubType, local_dir: &Path, folder_id: Option<String>, exists: bool, options: &PushOptions) -> Plan {
+    let entries = walk(local_dir, &options.filter).unwrap_or_else(|e| {
+        error!("Failed at reading the folder '{}' - {}", local_dir.to_string_lossy(), e);
+        exit(misc::EXIT_CODE_027)
+    });
+    let encrypted = options.encryption_pub_key.is_some();
+
+    // Encrypted files are uploaded with the extension '.age'
+    let remote_path = |entry: &LocalEntry| match entry.size {
+        Some(_) if encrypted => format!("{}.age", entry.path),
+        _ => entry.path.clone(),
+    };
+
+    // A destination there is not created yet (dry run) is empty, so everything is uploaded
+    let remote_entries = if exists { tree(hub, folder_id.clone()).await } else { Vec::new() };
+    let remote: HashMap<(String, bool), &LogicalFile> = remote_entries.iter()
+        .map(|entry| ((entry.path.clone(), entry.file.is_folder()), &entry.file))
+        .collect();
+
+    let mut folders: HashMap<String, Option<String>> = remote_entries.iter()
+        .filter(|entry| entry.file.is_folder())
+        .map(|entry| (entry.path.clone(), entry.file.id.clone()))
+        .collect();
+    folders.insert(String::new(), folder_id);
+
+    let mut actions = Vec::new();
+    for entry in &entries {
+        let is_folder = entry.size.is_none();
+        let action = match remote.get(&(remote_path(entry), is_folder)) {
+            None if is_folder => SyncAction::Mkdir,
+            None => SyncAction::Upload,
+            Some(_) if is_folder => continue,
+            Some(file) if entry.is_unchanged(file, encrypted) => {
+                debug!("Skipping '{}', because it is unchanged", entry.path);
+                continue;
+            }
+            Some(_) => SyncAction::Update,
+        };
+
+        let existing = remote.get(&(remote_path(entry), is_folder));
+        actions.push(PlannedAction {
+            action,
+            path: entry.path.clone(),
+            size: entry.size,
+            id: existing.and_then(|file| file.id.clone()),
+            local_path: Some(entry.local_path.clone()),
+            file: None,
+            existing_files: existing.map(|file| file.parts.iter().chain(file.parity.iter()).cloned().collect()).unwrap_or_default(),
+        });
+    }
+
+    if options.delete {
+        let local: HashSet<(String, bool)> = entries.iter()
+            .map(|entry| (remote_path(entry), entry.size.is_none()))
+            .collect();
+
+        let mut deleted_folders: Vec<String> = Vec::new();
+        for entry in &remote_entries {
+            let is_folder = entry.file.is_folder();
+            // The excluded files are left untouched, and the files in a deleted folder are deleted with it
+            let path = if encrypted && !is_folder { entry.path.trim_end_matches(".age") } else { &entry.path };
+            if local.contains(&(entry.path.clone(), is_folder))
+                || !options.filter.matches(path, is_folder)
+                || deleted_folders.iter().any(|folder| entry.path.starts_with(&format!("{}/", folder))) {
+                continue;
+            }
+
+            if is_folder {
+                deleted_folders.push(entry.path.clone());
+            }
+            actions.push(PlannedAction {
+                action: SyncAction::Delete,
+                path: entry.path.clone(),
+                size: entry.file.size.map(|size| size.max(0) as u64),
+                id: entry.file.id.clone(),
+                local_path: None,
+                file: Some(entry.file.parts[0].clone()),
+                existing_files: Vec::new(),
+            });
+        }
+    }
+
+    Plan { actions, folders }
+}
+
+// The actions a push would take, without taking any of them
+pub async fn plan_push(hub: &HubType, local_dir: &Path, folder_id: Option<String>, exists: bool, options: &PushOptions) -> Vec<PlannedAction> {
+    plan(hub, local_dir, folder_id, exists, options).await.actions
+}
+
+// Uploads the files in the local folder there are new or changed to the folder (`None` is 'My Drive') file by file,
+// the folders there does not exist are created, and existing files are replaced (after the new file is uploaded).
+// The result is incomplete, if one of the files failed, then no files are deleted
+pub async fn push(hub: &HubType, local_dir: &Path, folder_id: Option<String>, options: &PushOptions) -> UploadResult {
+    let Plan { actions, mut folders } = plan(hub, local_dir, folder_id, true, options).await;
+
+    // The folders are created first (a parent is always listed before the folders in it)
+    for action in actions.iter().filter(|action| action.action == SyncAction::Mkdir) {
+        let parent = folders[parent_path(&action.path)].clone();
+        let folder = create_folder(hub, action.path.rsplit('/').next().unwrap(), parent).await;
+        folders.insert(action.path.clone(), folder.id);
+    }
+
+    let results: Vec<UploadResult> = stream::iter(actions.iter()
+        .filter(|action| matches!(action.action, SyncAction::Upload | SyncAction::Update)))
+        .map(|action| push_file(hub, action, folders[parent_path(&action.path)].clone(), options))
+        .buffer_unordered(options.jobs.max(1))
+        .collect()
+        .await;
+
+    let mut push_result = UploadResult {
+        complete: true,
+        uploaded_files: vec![],
+        deleted_files: vec![],
+        data_size: None,
+        parity_failed: false,
+    };
+    for result in results {
+        push_result.complete &= result.complete;
+        push_result.uploaded_files.extend(result.uploaded_files);
+        push_result.deleted_files.extend(result.deleted_files);
+    }
+
+    let deletes: Vec<&PlannedAction> = actions.iter().filter(|action| action.action == SyncAction::Delete).collect();
+    if !push_result.complete && !deletes.is_empty() {
+        warn!("Not deleting the {} file(s) missing locally, because the push is incomplete", deletes.len());
+        return push_result;
+    }
+    for action in deletes {
+        info!("Deleting '{}', because it does not exist locally", action.path);
+        push_result.deleted_files.extend(delete_all(hub, action.file.as_ref().unwrap(), options.trash).await);
+    }
+
+    push_result
+}
+
+async fn push_file(hub: &HubType, action: &PlannedAction, parent_folder_id: Option<String>, options: &PushOptions) -> UploadResult {
+    let local_path = action.local_path.as_ref().unwrap();
+    let file = match fs::File::open(local_path) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed at reading the file '{}' - {}", local_path.to_string_lossy(), e);
+            return UploadResult { complete: false, uploaded_files: vec![], deleted_files: vec![], data_size: None, parity_failed: false };
+        }
+    };
+
+    info!("Pushing '{}' ({})", action.path, action.action.as_str());
+    let size = part_size(action.size.unwrap(), options.part_size, options.encryption_pub_key.is_some());
+    upload(
+        hub,
+        file,
+        size as usize,
+        action.path.rsplit('/').next().unwrap().to_owned(),
+        parent_folder_id,
+        // The remote tree is already known, so the folder is not listed again for every file
+        &UploadOptions {
+            replace: action.action == SyncAction::Update,
+            encryption_pub_key: options.encryption_pub_key.clone(),
+            existing_files: Some(action.existing_files.clone()),
+            ..Default::default()
+        },
+    ).await
+}
+
+// Downloads the files below the folder (`None` is 'My Drive') into the local folder file by file,
+// existing local files are overwritten. Returns the files and folders there was downloaded
+pub async fn pull(hub: &HubType, folder_id: Option<String>, local_dir: &Path, filter: &MirrorFilter, jobs: usize) -> Vec<TreeEntry> {
+    let create_dir = |path: &Path| {
+        fs::create_dir_all(path).unwrap_or_else(|e| {
+            error!("Failed at creating the folder '{}' - {}", path.to_string_lossy(), e);
+            exit(misc::EXIT_CODE_027)
+        })
+    };
+    create_dir(local_dir);
+
+    // The folders with an unsafe name, everything below them is skipped as well
+    let mut skipped_folders: Vec<String> = Vec::new();
+    let entries: Vec<TreeEntry> = tree(hub, folder_id).await
+        .into_iter()
+        .filter(|entry| filter.matches(&entry.path, entry.file.is_folder()))
+        .filter(|entry| {
+            if skipped_folders.iter().any(|prefix| entry.path.starts_with(prefix)) {
+                return false;
+            }
+            if !is_safe_name(&entry.file.name) {
+                warn!("Skipping '{}', because the name cannot be used as a local name", entry.path);
+                skipped_folders.push(format!("{}/", entry.path));
+                return false;
+            }
+            true
+        })
+        .filter(|entry| {
+            // Google Docs, Sheets etc. have no content there can be downloaded
+            let is_google_file = !entry.file.is_folder() && entry.file.mime_type.as_deref()
+                .is_some_and(|mime_type| mime_type.starts_with("application/vnd.google-apps."));
+            if is_google_file {
+                warn!("Skipping '{}', because it is a Google Docs file", entry.path);
+            }
+            !is_google_file
+        })
+        .collect();
+
+    for entry in entries.iter().filter(|entry| entry.file.is_folder()) {
+        create_dir(&local_dir.join(&entry.path));
+    }
+
+    stream::iter(entries.iter().filter(|entry| !entry.file.is_folder()))
+        .map(|entry| pull_file(hub, entry, local_dir))
+        .buffer_unordered(jobs.max(1))
+        .collect::<Vec<()>>()
+        .await;
+
+    entries
+}
+
+async fn pull_file(hub: &HubType, entry: &TreeEntry, local_dir: &Path) {
+    let local_path = local_dir.join(&entry.path);
+    let mut file = fs::File::create(&local_path).unwrap_or_else(|e| {
+        error!("Failed at creating the file '{}' - {}", local_path.to_string_lossy(), e);
+        exit(misc::EXIT_CODE_027)
+    });
+
+    info!("Pulling '{}'", entry.path);
+    download_parts(hub, entry.file.parts.clone(), Some(&mut file), None).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_safe_name, part_size, LocalEntry, MirrorFilter};
+    use crate::crypto;
+    use crate::drive::list::group_files;
+    use chrono::{DateTime, Utc};
+    use google_drive3::api::File;
+    use std::fs;
+
+    #[test]
+    fn test_010_mirror_filter() {
+        let filter = MirrorFilter::new(&["*.txt".to_owned()], &["target".to_owned(), "*.tmp.txt".to_owned()]);
+        assert!(filter.matches("notes.txt", false));
+        assert!(filter.matches("docs/notes.txt", false));
+        assert!(!filter.matches("image.png", false));
+        assert!(!filter.matches("docs/notes.tmp.txt", false));
+        // Folders are not matched against the include globs, but everything below an excluded folder is excluded
+        assert!(filter.matches("docs", true));
+        assert!(!filter.matches("target", true));
+        assert!(!filter.matches("src/target/notes.txt", false));
+
+        let filter = MirrorFilter::default();
+        assert!(filter.matches("image.png", false));
+    }
+
+    #[test]
+    fn test_020_part_size() {
+        assert_eq!(1, part_size(0, None, false));
+        assert_eq!(100, part_size(100, None, false));
+        assert_eq!(100, part_size(100, Some(1024), false));
+        assert_eq!(1024, part_size(4096, Some(1024), false));
+        assert_eq!(crypto::encrypted_size(100), part_size(100, None, true));
+        assert_eq!(1024, part_size(1024, Some(1024), true));
+    }
+
+    #[test]
+    fn test_030_is_unchanged() {
+        let path = std::env::temp_dir().join(format!("pipe2drive-mirror-{}.txt", std::process::id()));
+        fs::write(&path, "hello").unwrap();
+        let modified_time = DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z").unwrap().with_timezone(&Utc);
+        let local = LocalEntry { path: "hello.txt".to_owned(), local_path: path.clone(), size: Some(5), modified_time: Some(modified_time) };
+
+        let part = |name: &str, size: &str, md5: Option<&str>, modified_time: &str| File {
+            id: Some(name.to_owned()),
+            name: Some(name.to_owned()),
+            mime_type: Some("application/octet-stream".to_owned()),
+            size: Some(size.to_owned()),
+            md5_checksum: md5.map(|md5| md5.to_owned()),
+            modified_time: Some(modified_time.to_owned()),
+            description: Some("0".to_owned()),
+            ..Default::default()
+        };
+
+        // A single part is compared by the checksum, no matter when it was modified
+        let remote = group_files(vec![part("hello.txt", "5", Some("5d41402abc4b2a76b9719d911017c592"), "2026-10-01T00:00:00Z")]).remove(0);
+        assert!(local.is_unchanged(&remote, false));
+        let remote = group_files(vec![part("hello.txt", "5", Some("00000000000000000000000000000000"), "2026-10-19T00:00:00Z")]).remove(0);
+        assert!(!local.is_unchanged(&remote, false));
+        let remote = group_files(vec![part("hello.txt", "6", Some("5d41402abc4b2a76b9719d911017c592"), "2026-10-19T00:00:00Z")]).remove(0);
+        assert!(!local.is_unchanged(&remote, false));
+
+        // Multiple parts (and encrypted files) are compared by the modified time
+        let parts = |modified_time: &str| group_files(vec![
+            part("hello.txt.000", "3", None, modified_time),
+            part("hello.txt.001", "2", None, modified_time),
+        ]).remove(0);
+        assert!(local.is_unchanged(&parts("2026-10-19T00:00:00Z"), false));
+        assert!(!local.is_unchanged(&parts("2026-10-01T00:00:00Z"), false));
+        assert!(local.is_unchanged(&parts("2026-10-19T00:00:00Z"), true));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_040_is_safe_name() {
+        assert!(is_safe_name("notes.txt"));
+        assert!(is_safe_name("..notes"));
+        assert!(!is_safe_name(""));
+        assert!(!is_safe_name("."));
+        assert!(!is_safe_name(".."));
+        assert!(!is_safe_name("/etc/passwd"));
+        assert!(!is_safe_name("../notes.txt"));
+        assert!(!is_safe_name("docs\\notes.txt"));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::info::FIELDS;
+use crate::drive::list::FOLDER_MIME_TYPE;
+use crate::drive::path::{children, split_path};
+use crate::misc;
+use google_drive3::api::File;
+use std::io::Cursor;
+use std::process::exit;
+
+pub async fn create_folder(hub: &HubType, name: &str, parent_folder_id: Option<String>) -> File {
+    let req = File {
+        name: Some(name.to_owned()),
+        mime_type: Some(FOLDER_MIME_TYPE.to_owned()),
+        parents: parent_folder_id.or_else(auth::default_folder).map(|id| vec![id]),
+        ..Default::default()
+    };
+
+    let (_, folder) = hub.files()
+        .create(req)
+        .supports_all_drives(true)
+        .param("fields", FIELDS)
+        .add_scope(auth::scope())
+        // The create call does not support metadata only requests, so an empty file is uploaded
+        .upload(Cursor::new(Vec::new()), FOLDER_MIME_TYPE.parse().unwrap())
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed at creating the folder '{}' - {}", name, e);
+            auth::log_scope_hint(&e);
+            exit(misc::EXIT_CODE_019)
+        });
+
+    info!("Created the folder: '{}'", name);
+    folder
+}
+
+// Walks the path from 'My Drive' and creates the folders there are missing, but only the
+// last folder if `parents` is false (like `mkdir` and `mkdir -p`). Returns the last folder
+pub async fn mkdir(hub: &HubType, path: &str, parents: bool) -> File {
+    let components = split_path(path);
+    if components.is_empty() {
+        error!("The path '{}' does not contain any folder names", path);
+        exit(misc::EXIT_CODE_018)
+    }
+
+    let mut folder_id: Option<String> = None;
+    let mut folder: Option<File> = None;
+    for (index, component) in components.iter().enumerate() {
+        let is_last = index == components.len() - 1;
+
+        let mut matches: Vec<File> = children(hub, folder_id.clone()).await
+            .into_iter()
+            .filter(|file| file.name == *component && file.is_folder())
+            .map(|mut file| file.parts.remove(0))
+            .collect();
+
+        if matches.len() > 1 {
+            error!("The path '{}' is ambiguous, there are multiple folders named '{}', use one of the IDs instead: {}",
+                   path,
+                   component,
+                   matches.iter().map(|file| file.id.clone().unwrap_or_default()).collect::<Vec<String>>().join(", "));
+            exit(misc::EXIT_CODE_018)
+        }
+
+        let next = match matches.pop() {
+            Some(existing) if is_last && !parents => {
+                error!("The folder '{}' already exists (ID: {})", path, existing.id.as_ref().unwrap());
+                exit(misc::EXIT_CODE_019)
+            }
+            Some(existing) => existing,
+            None if is_last || parents => create_folder(hub, component, folder_id.clone()).await,
+            None => {
+                error!("The path '{}' does not exist - '{}' was not found, use '-p' to create the missing folders", path, component);
+                exit(misc::EXIT_CODE_018)
+            }
+        };
+
+        folder_id = next.id.clone();
+        folder = Some(next);
+    }
+
+    folder.unwrap()
+}
@@ -1,18 +1,50 @@
+mod about;
+mod catalog;
+mod copy;
+mod dedup;
 mod delete;
 mod download;
+mod drives;
+mod find;
 mod info;
 mod list;
+mod mkdir;
+mod mirror;
+mod parity;
+mod path;
+mod prune;
 mod rename;
 mod set_description;
+mod set_parents;
+mod share;
+mod set_trashed;
 mod upload;
-
-pub use delete::delete;
+mod usage;
+mod verify;
+
+pub use about::about;
+pub use catalog::upload_catalog;
+pub use copy::copy_all;
+pub use dedup::{gc, upload_dedup};
+pub use delete::{delete, delete_all};
 pub use download::download;
+pub use drives::{list_drives, select_drive};
+pub use find::{find, has_tags, parse_mime_type, FindFilter};
+pub(crate) use find::glob_to_regex;
 pub use info::info;
-pub use list::list;
-pub use rename::rename;
-pub use set_description::set_description;
-pub use upload::{upload, UploadResult};
+pub use list::{group_files, list, LogicalFile, FOLDER_MIME_TYPE};
+pub use mkdir::mkdir;
+pub use mirror::{plan_push, pull, push, MirrorFilter, PlannedAction, PushOptions};
+pub use path::{find_folder, is_path, resolve, resolve_folder, tree, TreeEntry};
+pub use prune::prune;
+pub use rename::{rename, rename_all};
+pub use set_description::{set_description, set_description_all};
+pub use set_parents::move_all;
+pub use set_trashed::set_trashed_all;
+pub use share::{permissions_all, revoke_all, share_all, Grantee, SharedPermission, ShareRole};
+pub use upload::{upload, UploadOptions, UploadResult};
+pub use usage::{check_quota, du, quota, DiskUsage, Quota};
+pub use verify::{verify, VerifyReport};
 
 #[cfg(test)]
 use download::download_overwrite_options;
@@ -27,14 +59,14 @@ mod tests {
     use serde_json;
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use crate::auth::{CLIENT_SECRET_ENV, CLIENT_SECRET_FILE, CLIENT_TOKEN_ENV, CLIENT_TOKEN_FILE};
+    use crate::auth::{CLIENT_SECRET_ENV, CLIENT_SECRET_FILE, CLIENT_TOKEN_ENV, CLIENT_TOKEN_FILE, SERVICE_ACCOUNT_ENV};
     use crate::misc::{config_file, parse_data_size};
     use crate::pipe_buffer::TestBuffer;
     use crate::{auth, drive};
     use crate::misc::tests::{verify_test_buffer_data};
 
     use function_name::named;
-    use google_drive3::oauth2::parse_application_secret;
+    use google_drive3::oauth2::{parse_application_secret, parse_service_account_key};
     use serde_json::Value;
 
     macro_rules! aw {
@@ -45,6 +77,13 @@ mod tests {
 
     #[test]
     fn test_000_check_needed_files_exists() {
+        // A service account key replaces both the client secret and the client token
+        if let Ok(service_account_data) = env::var(SERVICE_ACCOUNT_ENV) {
+            let service_account_key_is_ok = parse_service_account_key(&service_account_data).is_ok();
+            assert!(service_account_key_is_ok, "The service account key data is invalid");
+            return;
+        }
+
         if let Ok(client_secret_data) = env::var(CLIENT_SECRET_ENV) {
             let app_secret = parse_application_secret(&client_secret_data);
             let app_secret_is_ok = app_secret.is_ok();
@@ -68,7 +107,7 @@ mod tests {
     #[test]
     fn test_010_upload_3_files_set_diff_size() {
         let data_size = parse_data_size("7 Kib").as_u64();
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
 
         let result = aw!(drive::upload::<TestBuffer>(
             &hub,
@@ -76,9 +115,7 @@ mod tests {
             parse_data_size("3 KiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            None,
+            &drive::UploadOptions { replace: true, ..Default::default() },
         ));
 
         assert_eq!(3, result.uploaded_files.len());
@@ -102,7 +139,7 @@ mod tests {
     #[test]
     fn test_020_upload_1_files_set_diff_size() {
         let data_size = parse_data_size("1 Kib").as_u64();
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
 
         let result = aw!(drive::upload::<TestBuffer>(
             &hub,
@@ -110,9 +147,7 @@ mod tests {
             parse_data_size("2 KiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            None,
+            &drive::UploadOptions { replace: true, ..Default::default() },
         ));
 
         assert_eq!(1, result.uploaded_files.len());
@@ -136,7 +171,7 @@ mod tests {
     #[test]
     fn test_030_upload_1_file_set_exact_size() {
         let data_size = parse_data_size("1 Kib").as_u64();
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
 
         let result = aw!(drive::upload::<TestBuffer>(
             &hub,
@@ -144,9 +179,7 @@ mod tests {
             parse_data_size("1 KiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            None,
+            &drive::UploadOptions { replace: true, ..Default::default() },
         ));
 
         assert_eq!(1, result.uploaded_files.len());
@@ -170,7 +203,7 @@ mod tests {
     #[test]
     fn test_040_upload_3_big_files_set_diff_size() {
         let data_size = parse_data_size("50 MiB").as_u64();
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
 
         let result = aw!(drive::upload::<TestBuffer>(
             &hub,
@@ -178,9 +211,7 @@ mod tests {
             parse_data_size("20 MiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            None,
+            &drive::UploadOptions { replace: true, ..Default::default() },
         ));
 
         assert_eq!(3, result.uploaded_files.len());
@@ -204,7 +235,7 @@ mod tests {
     #[test]
     fn test_050_upload_1_big_files_set_diff_size() {
         let data_size = parse_data_size("30 MiB").as_u64();
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
 
         let result = aw!(drive::upload::<TestBuffer>(
             &hub,
@@ -212,9 +243,7 @@ mod tests {
             parse_data_size("60 MiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            None,
+            &drive::UploadOptions { replace: true, ..Default::default() },
         ));
 
         assert_eq!(1, result.uploaded_files.len());
@@ -238,7 +267,7 @@ mod tests {
     #[test]
     fn test_060_upload_1_big_file_set_exact_size() {
         let data_size = parse_data_size("50 MiB").as_u64();
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
 
         let result = aw!(drive::upload::<TestBuffer>(
             &hub,
@@ -246,9 +275,7 @@ mod tests {
             parse_data_size("50 MiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            None,
+            &drive::UploadOptions { replace: true, ..Default::default() },
         ));
 
         assert_eq!(1, result.uploaded_files.len());
@@ -280,16 +307,14 @@ mod tests {
         let data_size = parse_data_size("5 Kib").as_u64();
 
         // Upload file
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
         let upload_result = aw!(drive::upload::<TestBuffer>(
             &hub,
             TestBuffer::new(data_size as usize),
             parse_data_size("8 KiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            Some(public_key),
+            &drive::UploadOptions { replace: true, encryption_pub_key: Some(public_key), ..Default::default() },
         ));
 
         // Verify that only one file was uploaded
@@ -375,16 +400,14 @@ mod tests {
         let data_size = parse_data_size("15 kiB").as_u64();
 
         // Upload file
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
         let upload_result = aw!(drive::upload::<TestBuffer>(
             &hub,
             TestBuffer::new(data_size as usize),
             parse_data_size("6 kiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            Some(public_key),
+            &drive::UploadOptions { replace: true, encryption_pub_key: Some(public_key), ..Default::default() },
         ));
 
         // Verify that only one file was uploaded
@@ -437,16 +460,14 @@ mod tests {
         let data_size = parse_data_size("6 kiB").as_u64();
 
         // Upload file
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
         let upload_result = aw!(drive::upload::<TestBuffer>(
             &hub,
             TestBuffer::new(data_size as usize),
             parse_data_size("15 kiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            Some(public_key),
+            &drive::UploadOptions { replace: true, encryption_pub_key: Some(public_key), ..Default::default() },
         ));
 
         // Verify that only one file was uploaded
@@ -499,16 +520,14 @@ mod tests {
         let data_size = parse_data_size("50 MiB").as_u64();
 
         // Upload file
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
         let upload_result = aw!(drive::upload::<TestBuffer>(
             &hub,
             TestBuffer::new(data_size as usize),
             parse_data_size("20 MiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            Some(public_key),
+            &drive::UploadOptions { replace: true, encryption_pub_key: Some(public_key), ..Default::default() },
         ));
 
         // Verify that only one file was uploaded
@@ -561,16 +580,14 @@ mod tests {
         let data_size = parse_data_size("30 MiB").as_u64();
 
         // Upload file
-        let hub = aw!(auth::auth(None, None));
+        let hub = aw!(auth::auth(None, None, auth::AuthFlow::Redirect, None, None));
         let upload_result = aw!(drive::upload::<TestBuffer>(
             &hub,
             TestBuffer::new(data_size as usize),
             parse_data_size("60 MiB").as_u64() as usize,
             format!("{}.txt", function_name!()).to_owned(),
             None,
-            false,
-            true,
-            Some(public_key),
+            &drive::UploadOptions { replace: true, encryption_pub_key: Some(public_key), ..Default::default() },
         ));
 
         // Verify that only one file was uploaded
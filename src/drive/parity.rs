@@ -0,0 +1,296 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::download::download_parts;
+use crate::drive::info::FIELDS;
+use crate::drive::{delete, list};
+use crate::drive::upload::{UploadOptions, UploadResult};
+use crate::erasure::{self, ParityEncoder, ParityInfo, TempFile};
+use crate::misc;
+use google_drive3::api::File;
+use std::borrow::BorrowMut;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::exit;
+
+// The parity parts of FILE_NAME are named FILE_NAME.parity.000, FILE_NAME.parity.001, ...
+pub(crate) fn parity_regex(filename: &str) -> String {
+    format!(r#"^{}\.parity\.[0-9]+$"#, regex::escape(filename))
+}
+
+fn parity_name(filename: &str, index: usize) -> String {
+    format!("{}.parity.{:0>3}", filename, index)
+}
+
+// The name of a parity part of the file, when the file is renamed (the parity parts keeps their suffix)
+pub(crate) fn renamed_parity(parity_name: &str, new_filename: &str) -> String {
+    let suffix = parity_name.rfind(".parity.").map_or("", |index| &parity_name[index..]);
+    format!("{}{}", new_filename, suffix)
+}
+
+// The name of the file, without the part suffix (.000, .001, ...)
+pub(crate) fn logical_name(part_name: &str) -> &str {
+    match misc::part_suffix(part_name) {
+        Some(suffix) => &part_name[..part_name.len() - suffix.len()],
+        None => part_name,
+    }
+}
+
+// Uploads the parity parts calculated while the data parts were uploaded, the parity info is saved in the
+// description of every parity part (and the properties of the upload on them as well).
+// If a parity part fails, the uploaded parity parts are deleted again
+pub(crate) async fn upload_parity(
+    hub: &HubType,
+    encoder: ParityEncoder,
+    filename: &str,
+    parent_folder_id: Option<String>,
+    padding: u64,
+    options: &UploadOptions,
+    upload_status: &mut UploadResult,
+) -> Result<(), String> {
+    let (mut info, mut temp_files) = encoder.finish().map_err(|e| e.to_string())?;
+    info.padding = padding;
+    let description = serde_json::to_string(&info).unwrap();
+
+    let mut uploaded: Vec<File> = Vec::new();
+    let mut failure = None;
+    for (index, temp_file) in temp_files.iter_mut().enumerate() {
+        let name = parity_name(filename, index);
+        let req = File {
+            name: Some(name.clone()),
+            parents: parent_folder_id.clone().map(|id| vec![id]),
+            description: Some(description.clone()),
+            properties: (!options.properties.is_empty()).then(|| options.properties.clone()),
+            ..Default::default()
+        };
+
+        let reader = match temp_file.rewind() {
+            Ok(reader) => reader,
+            Err(e) => {
+                failure = Some(format!("failed at reading the parity part '{}' - {}", name, e));
+                break;
+            }
+        };
+        let result = hub.files()
+            .create(req)
+            .supports_all_drives(true)
+            .param("fields", FIELDS)
+            .add_scope(auth::scope())
+            .upload_resumable(reader, "application/octet-stream".parse().unwrap())
+            .await;
+
+        match result {
+            Ok((_, file)) => {
+                info!("Uploaded the parity part: '{}'", name);
+                uploaded.push(file);
+            }
+            Err(e) => {
+                auth::log_scope_hint(&e);
+                failure = Some(format!("failed at uploading the parity part '{}' - {}", name, e));
+                break;
+            }
+        }
+    }
+
+    match failure {
+        Some(failure) => {
+            for file in uploaded {
+                delete(hub, &file).await;
+                upload_status.deleted_files.push(file);
+            }
+            Err(failure)
+        }
+        None => {
+            upload_status.uploaded_files.append(&mut uploaded);
+            Ok(())
+        }
+    }
+}
+
+// The parts of a file, which can be rebuilt from the parity parts
+pub(crate) struct Repair {
+    name: String,
+    info: ParityInfo,
+    parts: Vec<Option<File>>,
+    parity: Vec<Option<File>>,
+}
+
+// Finds the parity parts of the file (the parts are given), and the parity info saved in the description of them
+pub(crate) async fn find_parity(hub: &HubType, info: &File, files: &[File]) -> Option<(String, ParityInfo, Vec<File>)> {
+    let name = logical_name(files.first()?.name.as_ref()?).to_owned();
+    let siblings = list(hub, info.parents.as_ref().and_then(|parents| parents.first().cloned())).await;
+    let mut parity_files = misc::file_filter(&parity_regex(&name), &siblings);
+    if parity_files.is_empty() {
+        return None;
+    }
+    parity_files.sort_by(|f1, f2| f1.name.cmp(&f2.name));
+
+    let parity_info = parity_files.iter()
+        .find_map(|file| file.description.as_ref().and_then(|d| serde_json::from_str::<ParityInfo>(d).ok()));
+    match parity_info {
+        Some(parity_info) => Some((name, parity_info, parity_files)),
+        None => {
+            warn!("The parity parts of '{}' have no parity info, they are ignored", name);
+            None
+        }
+    }
+}
+
+// The index of a part (0 for a file there is not split), `None` if the name does not match the parity info
+pub(crate) fn part_index(parity_info: &ParityInfo, part_name: &str) -> Option<usize> {
+    match misc::part_suffix(part_name) {
+        Some(suffix) if parity_info.data_parts > 1 => suffix[1..].parse::<usize>().ok(),
+        None if parity_info.data_parts == 1 => Some(0),
+        _ => None,
+    }
+}
+
+// Checks the parts against the MD5 checksums in the parity info.
+// Returns `None` if the file has no parity parts, or all the parts are fine
+pub(crate) async fn check(hub: &HubType, info: &File, files: &[File]) -> Option<Repair> {
+    let (name, parity_info, parity_files) = find_parity(hub, info, files).await?;
+
+    let mut parts: Vec<Option<File>> = vec![None; parity_info.data_parts];
+    for file in files {
+        let file_name = file.name.as_ref().unwrap();
+        match part_index(&parity_info, file_name) {
+            Some(index) if index < parts.len() => {
+                let intact = file.md5_checksum.as_ref() == Some(&parity_info.md5[index])
+                    && file.size.as_ref().and_then(|s| s.parse::<u64>().ok()) == Some(parity_info.part_size);
+                if intact {
+                    parts[index] = Some(file.clone());
+                } else {
+                    warn!("The part '{}' is damaged (the MD5 checksum does not match)", file_name);
+                }
+            }
+            _ => {
+                warn!("The part '{}' is not covered by the parity parts, they are ignored", file_name);
+                return None;
+            }
+        }
+    }
+
+    let missing = parts.iter().filter(|part| part.is_none()).count();
+    if missing == 0 {
+        return None;
+    }
+
+    let mut parity: Vec<Option<File>> = vec![None; parity_info.parity_parts];
+    for file in parity_files {
+        let index = misc::part_suffix(file.name.as_ref().unwrap()).and_then(|suffix| suffix[1..].parse::<usize>().ok());
+        let size = file.size.as_ref().and_then(|s| s.parse::<u64>().ok());
+        if let Some(slot) = index.and_then(|index| parity.get_mut(index)) {
+            if size == Some(parity_info.part_size) {
+                *slot = Some(file);
+            }
+        }
+    }
+
+    let available = parity.iter().filter(|part| part.is_some()).count();
+    if missing > available {
+        error!("{} part(s) of '{}' are missing or damaged, but only {} parity part(s) are available, \
+                the file cannot be rebuilt", missing, name, available);
+        exit(misc::EXIT_CODE_029);
+    }
+    warn!("Rebuilding {} part(s) of '{}' from the parity parts", missing, name);
+
+    Some(Repair { name, info: parity_info, parts, parity })
+}
+
+async fn download_temp(hub: &HubType, file: Option<File>) -> io::Result<Option<TempFile>> {
+    match file {
+        Some(file) => {
+            let mut temp_file = TempFile::new()?;
+            download_parts(hub, vec![file], Some(&mut temp_file.file), Some(0)).await;
+            temp_file.rewind()?;
+            Ok(Some(temp_file))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn rebuild(hub: &HubType, repair: Repair) -> io::Result<(ParityInfo, Vec<TempFile>)> {
+    let Repair { info, parts, parity, .. } = repair;
+
+    // The intact parts are downloaded to temporary files, because all the parts are needed to rebuild a block
+    let mut part_files = Vec::new();
+    for part in parts {
+        part_files.push(download_temp(hub, part).await?);
+    }
+    let mut parity_files = Vec::new();
+    for part in parity {
+        parity_files.push(download_temp(hub, part).await?);
+    }
+
+    let mut part_readers: Vec<Option<&mut fs::File>> = part_files.iter_mut()
+        .map(|part| part.as_mut().map(|temp_file| &mut temp_file.file))
+        .collect();
+    let mut parity_readers: Vec<Option<&mut fs::File>> = parity_files.iter_mut()
+        .map(|part| part.as_mut().map(|temp_file| &mut temp_file.file))
+        .collect();
+    let mut rebuilt = erasure::reconstruct(&info, &mut part_readers, &mut parity_readers)?.into_iter();
+
+    // The rebuilt parts are checked, in case a parity part is damaged
+    let mut result = Vec::new();
+    for (index, part) in part_files.into_iter().enumerate() {
+        let mut temp_file = match part {
+            Some(temp_file) => temp_file,
+            None => {
+                let mut temp_file = rebuilt.next().unwrap();
+                let mut context = md5::Context::new();
+                io::copy(temp_file.rewind()?, &mut context)?;
+                if format!("{:x}", context.finalize()) != info.md5[index] {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                              format!("the rebuilt part {} does not match the MD5 checksum", index)));
+                }
+                temp_file
+            }
+        };
+        temp_file.rewind()?;
+        result.push(temp_file);
+    }
+
+    Ok((info, result))
+}
+
+// Rebuilds the missing and damaged parts, and writes the file (without the filler bytes) to the stream
+pub(crate) async fn restore(hub: &HubType, repair: Repair, mut stream: Option<&mut dyn Write>) {
+    let name = repair.name.clone();
+    let (info, parts) = rebuild(hub, repair).await.unwrap_or_else(|e| {
+        error!("Failed at rebuilding '{}' - {}", name, e);
+        exit(misc::EXIT_CODE_029);
+    });
+
+    let mut file;
+    if stream.is_none() {
+        file = fs::File::create(&name).expect("Unable to open file");
+        stream = Some(file.borrow_mut());
+    }
+    let stream = stream.unwrap();
+
+    let part_count = parts.len();
+    for (index, mut part) in parts.into_iter().enumerate() {
+        let len = if index + 1 == part_count { info.part_size - info.padding } else { info.part_size };
+        io::copy(&mut (&mut part.file).take(len), stream).expect("failed to write data");
+    }
+
+    info!("Download of '{}' Completed", name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{logical_name, parity_name, parity_regex, renamed_parity};
+    use regex::Regex;
+
+    #[test]
+    fn test_010_parity_names() {
+        let re = Regex::new(&parity_regex("db.tar")).unwrap();
+        assert!(re.is_match(&parity_name("db.tar", 1)));
+        assert!(!re.is_match("db.tar.001"));
+        assert!(!re.is_match("db.tar.parity.001.replace"));
+
+        assert_eq!("db.tar", logical_name("db.tar.002"));
+        assert_eq!("db.tar", logical_name("db.tar"));
+
+        assert_eq!("db-old.tar.parity.001", renamed_parity("db.tar.parity.001", "db-old.tar"));
+    }
+}
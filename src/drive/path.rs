@@ -0,0 +1,166 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::info;
+use crate::drive::list::{group_files, list, LogicalFile, FOLDER_MIME_TYPE};
+use crate::misc;
+use google_drive3::api::File;
+use serde::{Deserialize, Serialize};
+use std::process::exit;
+
+// A file found by walking a folder tree, the path is relative to the folder the walk started from
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TreeEntry {
+    pub path: String,
+    pub depth: usize,
+    #[serde(flatten)]
+    pub file: LogicalFile,
+}
+
+// IDs never contains a '/', so everything containing one is considered a path,
+// e.g. 'backups/db/2026-10-18.tar' or '/backups' for a folder in 'My Drive'
+pub fn is_path(id_or_path: &str) -> bool {
+    id_or_path.contains('/')
+}
+
+pub fn split_path(path: &str) -> Vec<&str> {
+    path.split('/').filter(|component| !component.is_empty()).collect()
+}
+
+// The files in the folder (`None` is 'My Drive') there is not trashed, with the split files grouped
+pub(crate) async fn children(hub: &HubType, folder_id: Option<String>) -> Vec<LogicalFile> {
+    let files = list(hub, folder_id).await
+        .into_iter()
+        .filter(|file| !file.trashed.unwrap_or(false))
+        .collect();
+    group_files(files)
+}
+
+// Walks the path one folder at a time from 'My Drive' (or the shared drive). Because multiple files/folders
+// can have the same name, every matching folder is followed, and only if the path in
+// the end matches exactly one file, it is returned (the first part, if the file is split).
+// If the path does not exist, the component there was not found is returned
+async fn lookup_path(hub: &HubType, path: &str) -> Result<File, String> {
+    let components = split_path(path);
+    if components.is_empty() {
+        return Ok(info(hub, auth::shared_drive().unwrap_or("root")).await);
+    }
+
+    let mut folders: Vec<Option<String>> = vec![None];
+    let mut matches: Vec<LogicalFile> = Vec::new();
+    for (index, component) in components.iter().enumerate() {
+        let is_last = index == components.len() - 1;
+
+        matches.clear();
+        for folder_id in folders.drain(..) {
+            matches.extend(
+                children(hub, folder_id).await
+                    .into_iter()
+                    .filter(|file| file.name == *component && (is_last || file.is_folder()))
+            );
+        }
+
+        if matches.is_empty() {
+            return Err(component.to_string());
+        }
+
+        folders = matches.iter().map(|file| file.id.clone()).collect();
+    }
+
+    if matches.len() > 1 {
+        error!("The path '{}' is ambiguous, it matches multiple files, use one of the IDs instead: {}",
+               path,
+               matches.iter().map(|file| file.id.clone().unwrap_or_default()).collect::<Vec<String>>().join(", "));
+        exit(misc::EXIT_CODE_018)
+    }
+
+    let file = matches.remove(0).parts.remove(0);
+    debug!("Resolved the path '{}' to the ID: {}", path, file.id.as_ref().unwrap());
+    Ok(info(hub, file.id.as_ref().unwrap()).await)
+}
+
+// Stops if the path does not exist
+pub async fn resolve_path(hub: &HubType, path: &str) -> File {
+    lookup_path(hub, path).await.unwrap_or_else(|component| {
+        error!("The path '{}' does not exist - '{}' was not found", path, component);
+        exit(misc::EXIT_CODE_018)
+    })
+}
+
+// Returns the info of the file, no matter if it was selected by ID or by path
+pub async fn resolve(hub: &HubType, id_or_path: &str) -> File {
+    if is_path(id_or_path) {
+        resolve_path(hub, id_or_path).await
+    } else {
+        info(hub, id_or_path).await
+    }
+}
+
+pub async fn resolve_folder(hub: &HubType, id_or_path: Option<String>) -> Option<String> {
+    match id_or_path {
+        Some(path) if is_path(&path) => {
+            let folder = resolve_path(hub, &path).await;
+            if folder.mime_type.as_deref() != Some(FOLDER_MIME_TYPE) {
+                error!("The path '{}' is not a folder", path);
+                exit(misc::EXIT_CODE_018)
+            }
+            folder.id
+        }
+        id => id,
+    }
+}
+
+// Like `resolve_folder` for a PATH, but `None` if the folder does not exist (yet)
+pub async fn find_folder(hub: &HubType, path: &str) -> Option<String> {
+    let folder = lookup_path(hub, path).await.ok()?;
+    if folder.mime_type.as_deref() != Some(FOLDER_MIME_TYPE) {
+        error!("The path '{}' is not a folder", path);
+        exit(misc::EXIT_CODE_018)
+    }
+    folder.id
+}
+
+// Lists everything below the folder (`None` is 'My Drive'), depth first in alphabetical order
+pub async fn tree(hub: &HubType, folder_id: Option<String>) -> Vec<TreeEntry> {
+    let mut entries = Vec::new();
+
+    let mut stack = children_entries(hub, folder_id, "", 0).await;
+    while let Some(entry) = stack.pop() {
+        if entry.file.is_folder() {
+            let prefix = format!("{}/", entry.path);
+            stack.append(&mut children_entries(hub, entry.file.id.clone(), &prefix, entry.depth + 1).await);
+        }
+        entries.push(entry);
+    }
+
+    entries
+}
+
+// The entries are returned in reverse order, because they are used as a stack
+async fn children_entries(hub: &HubType, folder_id: Option<String>, prefix: &str, depth: usize) -> Vec<TreeEntry> {
+    let mut files = children(hub, folder_id).await;
+    files.sort_by(|f1, f2| f2.name.cmp(&f1.name));
+
+    files.into_iter()
+        .map(|file| TreeEntry { path: format!("{}{}", prefix, file.name), depth, file })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_path, split_path};
+
+    #[test]
+    fn test_010_is_path() {
+        assert!(is_path("backups/db/2026-10-18.tar"));
+        assert!(is_path("/backups"));
+        assert!(is_path("backups/"));
+        assert!(!is_path("1BxiMVs0XRA5nFMdKvBdBZjgmUUqptlbs74OgvE2upms"));
+        assert!(!is_path("root"));
+    }
+
+    #[test]
+    fn test_020_split_path() {
+        assert_eq!(vec!["backups", "db", "2026-10-18.tar"], split_path("/backups//db/2026-10-18.tar"));
+        assert!(split_path("/").is_empty());
+    }
+}
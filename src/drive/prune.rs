@@ -0,0 +1,50 @@
+use crate::auth::HubType;
+use crate::drive::delete::delete_all;
+use crate::drive::list::LogicalFile;
+use crate::drive::path::children;
+use crate::retention::{KeepPolicy, NameTemplate};
+use chrono::{DateTime, Local};
+
+// Applies the keep policy to the files in the folder (`None` is 'My Drive') matching the template,
+// the time of a file is read from the name, or if the template has no date/time fields,
+// the modified time is used. Returns the files there was (or with `dry_run` would be) deleted
+pub async fn prune(hub: &HubType,
+                   folder_id: Option<String>,
+                   template: &NameTemplate,
+                   policy: &KeepPolicy,
+                   dry_run: bool,
+                   trash: bool) -> Vec<LogicalFile> {
+    let mut files = Vec::new();
+    for file in children(hub, folder_id).await {
+        if file.is_folder() || !template.matches(&file.name) {
+            continue;
+        }
+
+        let time = if template.has_fields() {
+            template.timestamp(&file.name)
+        } else {
+            file.modified_time.as_deref()
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                .map(|time| time.with_timezone(&Local).naive_local())
+        };
+        match time {
+            Some(time) => files.push((time, file)),
+            None => warn!("Skipping the file '{}', because the time of it is unknown", file.name),
+        }
+    }
+
+    let (kept, removed) = policy.apply(files);
+    for file in &kept {
+        info!("Keeping '{}'", file.name);
+    }
+
+    for file in &removed {
+        if dry_run {
+            info!("Would delete '{}' (dry run)", file.name);
+            continue;
+        }
+        delete_all(hub, &file.parts[0], trash).await;
+    }
+
+    removed
+}
@@ -1,7 +1,12 @@
+use crate::auth;
 use crate::auth::HubType;
-use google_drive3::api::{File, Scope};
+use crate::drive::list::{create_file_list, create_parity_list};
+use crate::drive::parity;
+use crate::misc;
+use google_drive3::api::File;
 use google_drive3::Result;
 use google_drive3::hyper::{Body, Response};
+use std::process::exit;
 
 pub async fn rename(
     hub: &HubType,
@@ -14,7 +19,36 @@ pub async fn rename(
     hub.files()
         .update(file, file_id)
         .supports_all_drives(true)
-        .add_scope(Scope::Full)
+        .add_scope(auth::scope())
         .doit_without_upload()
         .await
 }
+
+// Renames all the parts of a file, which was split into multiple files, and the parity parts of it,
+// the parts keeps their suffix (.000, .001, ...)
+pub async fn rename_all(hub: &HubType, file: &File, new_name: &str) -> Vec<File> {
+    let mut files = create_file_list(hub, file).await;
+    let part_count = files.len();
+    files.append(&mut create_parity_list(hub, file).await);
+    let mut renamed_files = Vec::new();
+
+    for (index, file) in files.iter().enumerate() {
+        let old_name = file.name.as_ref().unwrap();
+        let name = match misc::part_suffix(old_name) {
+            _ if index >= part_count => parity::renamed_parity(old_name, new_name),
+            Some(suffix) if part_count > 1 => format!("{}{}", new_name, suffix),
+            _ => new_name.to_owned(),
+        };
+
+        let (_, renamed_file) = rename(hub, file.id.as_ref().unwrap(), name).await.unwrap_or_else(|e| {
+            error!("Failed at renaming the file '{}' - {}", old_name, e);
+            auth::log_scope_hint(&e);
+            exit(misc::EXIT_CODE_017);
+        });
+        info!("Renamed file: '{}' to '{}'", old_name, renamed_file.name.as_ref().unwrap());
+
+        renamed_files.push(renamed_file);
+    }
+
+    renamed_files
+}
@@ -1,7 +1,11 @@
+use crate::auth;
 use crate::auth::HubType;
-use google_drive3::api::{File, Scope};
+use crate::drive::list::create_file_list;
+use crate::misc;
+use google_drive3::api::File;
 use google_drive3::Result;
 use google_drive3::hyper::{Body, Response};
+use std::process::exit;
 
 pub async fn set_description(
     hub: &HubType,
@@ -14,7 +18,37 @@ pub async fn set_description(
     hub.files()
         .update(file, file_id)
         .supports_all_drives(true)
-        .add_scope(Scope::Full)
+        .add_scope(auth::scope())
         .doit_without_upload()
         .await
 }
+
+// Sets the description of all the parts of a file, which was split into multiple files.
+// The last part keeps the number of filler bytes (0x00) as the first line, because it is needed to download the file
+pub async fn set_description_all(hub: &HubType, file: &File, text: Option<&str>) -> Vec<File> {
+    let files = create_file_list(hub, file).await;
+    let mut described_files = Vec::new();
+
+    for (index, file) in files.iter().enumerate() {
+        let padding = if index == files.len() - 1 {
+            misc::padding_from_description(&file.description)
+        } else {
+            None
+        };
+
+        let (_, described_file) = set_description(
+            hub,
+            file.id.as_ref().unwrap(),
+            misc::build_description(padding, text),
+        ).await.unwrap_or_else(|e| {
+            error!("Failed at setting the description of the file '{}' - {}", file.name.as_ref().unwrap(), e);
+            auth::log_scope_hint(&e);
+            exit(misc::EXIT_CODE_017);
+        });
+        info!("Set the description of '{}'", file.name.as_ref().unwrap());
+
+        described_files.push(described_file);
+    }
+
+    described_files
+}
@@ -0,0 +1,53 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::info::FIELDS;
+use crate::drive::list::{check_name_free, create_file_list, create_parity_list};
+use crate::drive::parity;
+use crate::misc;
+use google_drive3::api::File;
+use google_drive3::Result;
+use google_drive3::hyper::{Body, Response};
+use std::process::exit;
+
+pub async fn set_parents(
+    hub: &HubType,
+    file_id: &str,
+    add_parents: &str,
+    remove_parents: &str,
+) -> Result<(Response<Body>, File)> {
+    hub.files()
+        .update(File::default(), file_id)
+        .add_parents(add_parents)
+        .remove_parents(remove_parents)
+        .supports_all_drives(true)
+        .param("fields", FIELDS)
+        .add_scope(auth::scope())
+        .doit_without_upload()
+        .await
+}
+
+// Moves all the parts of a file, which was split into multiple files, and the parity parts of it to the folder.
+// Only the parents are changed, so the description (with the number of filler bytes) is kept.
+// Stops if the folder already has a file with the name, unless DUPLICATE is set
+pub async fn move_all(hub: &HubType, file: &File, folder_id: &str, duplicate: bool) -> Vec<File> {
+    let mut files = create_file_list(hub, file).await;
+    let name = parity::logical_name(file.name.as_ref().unwrap());
+    check_name_free(hub, Some(folder_id.to_owned()), name, &files, duplicate).await;
+    files.append(&mut create_parity_list(hub, file).await);
+    let mut moved_files = Vec::new();
+
+    for file in &files {
+        let parents = file.parents.clone().unwrap_or_default().join(",");
+
+        let (_, moved_file) = set_parents(hub, file.id.as_ref().unwrap(), folder_id, &parents).await.unwrap_or_else(|e| {
+            error!("Failed at moving the file '{}' - {}", file.name.as_ref().unwrap(), e);
+            auth::log_scope_hint(&e);
+            exit(misc::EXIT_CODE_025);
+        });
+        info!("Moved '{}' to the folder '{}'", file.name.as_ref().unwrap(), folder_id);
+
+        moved_files.push(moved_file);
+    }
+
+    moved_files
+}
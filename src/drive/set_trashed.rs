@@ -0,0 +1,48 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::list::{create_file_list, create_parity_list};
+use crate::misc;
+use google_drive3::api::File;
+use google_drive3::Result;
+use google_drive3::hyper::{Body, Response};
+use std::process::exit;
+
+pub async fn set_trashed(
+    hub: &HubType,
+    file_id: &str,
+    trashed: bool,
+) -> Result<(Response<Body>, File)> {
+    let file = File {
+        trashed: Some(trashed),
+        ..Default::default()
+    };
+
+    hub.files()
+        .update(file, file_id)
+        .supports_all_drives(true)
+        .add_scope(auth::scope())
+        .doit_without_upload()
+        .await
+}
+
+// Moves all the parts of a file, which was split into multiple files, and the parity parts of it
+// to (or out of) the trash
+pub async fn set_trashed_all(hub: &HubType, file: &File, trashed: bool) -> Vec<File> {
+    let mut files = create_file_list(hub, file).await;
+    files.append(&mut create_parity_list(hub, file).await);
+    let mut updated_files = Vec::new();
+
+    for file in &files {
+        let (_, updated_file) = set_trashed(hub, file.id.as_ref().unwrap(), trashed).await.unwrap_or_else(|e| {
+            error!("Failed at moving the file '{}' {} the trash - {}",
+                   file.name.as_ref().unwrap(), if trashed { "to" } else { "out of" }, e);
+            auth::log_scope_hint(&e);
+            exit(misc::EXIT_CODE_009);
+        });
+        info!("Moved '{}' {} the trash", file.name.as_ref().unwrap(), if trashed { "to" } else { "out of" });
+
+        updated_files.push(updated_file);
+    }
+
+    updated_files
+}
@@ -0,0 +1,189 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::drive::list::create_file_list;
+use crate::misc;
+use google_drive3::api::{File, Permission};
+use serde::{Deserialize, Serialize};
+use std::process::exit;
+
+pub const PERMISSION_FIELDS: &str = "id,type,role,emailAddress,domain,displayName,allowFileDiscovery";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShareRole {
+    Reader,
+    Commenter,
+    Writer,
+}
+
+impl ShareRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ShareRole::Reader => "reader",
+            ShareRole::Commenter => "commenter",
+            ShareRole::Writer => "writer",
+        }
+    }
+}
+
+// Who the file is shared with
+#[derive(Clone, Debug)]
+pub enum Grantee {
+    User(String),
+    Group(String),
+    Domain(String),
+    // Anyone with the link, the file cannot be found by searching
+    Anyone,
+}
+
+impl Grantee {
+    fn permission(&self, role: ShareRole) -> Permission {
+        let mut permission = Permission {
+            role: Some(role.as_str().to_owned()),
+            ..Default::default()
+        };
+
+        match self {
+            Grantee::User(email) | Grantee::Group(email) => {
+                permission.type_ = Some(if matches!(self, Grantee::User(_)) { "user" } else { "group" }.to_owned());
+                permission.email_address = Some(email.to_owned());
+            }
+            Grantee::Domain(domain) => {
+                permission.type_ = Some("domain".to_owned());
+                permission.domain = Some(domain.to_owned());
+                permission.allow_file_discovery = Some(false);
+            }
+            Grantee::Anyone => {
+                permission.type_ = Some("anyone".to_owned());
+                permission.allow_file_discovery = Some(false);
+            }
+        }
+
+        permission
+    }
+}
+
+// A permission of a file, which was split into multiple files, and on how many of the parts it exists
+#[derive(Serialize, Deserialize)]
+pub struct SharedPermission {
+    #[serde(flatten)]
+    pub permission: Permission,
+    pub part_count: usize,
+    pub total_part_count: usize,
+}
+
+pub async fn permissions(hub: &HubType, file: &File) -> Vec<Permission> {
+    let mut permissions: Vec<Permission> = Vec::new();
+
+    let mut next_page_token: Option<String> = None;
+    while {
+        let mut build = hub.permissions().list(file.id.as_ref().unwrap())
+            .supports_all_drives(true)
+            .param("fields", format!("nextPageToken,permissions({})", PERMISSION_FIELDS).as_str());
+
+        if let Some(page_token) = next_page_token.as_deref() {
+            build = build.page_token(page_token)
+        }
+
+        let (_, permission_list) = build
+            .add_scope(auth::scope())
+            .doit()
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed at listing the permissions of the file '{}' - {}", file.name.as_ref().unwrap(), e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_024)
+            });
+
+        next_page_token = permission_list.next_page_token;
+        let mut tmp = permission_list.permissions.unwrap_or_default();
+        permissions.append(tmp.as_mut());
+
+        next_page_token.is_some()
+    } {}
+
+    permissions
+}
+
+// Lists the permissions of all the parts of a file, a permission (e.g. a user) has the same ID on every part
+pub async fn permissions_all(hub: &HubType, file: &File) -> Vec<SharedPermission> {
+    let files = create_file_list(hub, file).await;
+
+    let mut shared_permissions: Vec<SharedPermission> = Vec::new();
+    for file in &files {
+        for permission in permissions(hub, file).await {
+            match shared_permissions.iter_mut().find(|shared| shared.permission.id == permission.id) {
+                Some(shared) => shared.part_count += 1,
+                None => shared_permissions.push(SharedPermission {
+                    permission,
+                    part_count: 1,
+                    total_part_count: files.len(),
+                }),
+            }
+        }
+    }
+
+    shared_permissions
+}
+
+// Shares all the parts of a file, which was split into multiple files, and returns the permissions afterwards
+pub async fn share_all(hub: &HubType, file: &File, grantee: &Grantee, role: ShareRole, notify: bool) -> Vec<SharedPermission> {
+    for file in create_file_list(hub, file).await {
+        let mut build = hub.permissions()
+            .create(grantee.permission(role), file.id.as_ref().unwrap())
+            .supports_all_drives(true)
+            .param("fields", PERMISSION_FIELDS);
+
+        // Notification emails can only be sent to users and groups
+        if matches!(grantee, Grantee::User(_) | Grantee::Group(_)) {
+            build = build.send_notification_email(notify)
+        }
+
+        build.add_scope(auth::scope())
+            .doit()
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed at sharing the file '{}' - {}", file.name.as_ref().unwrap(), e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_024)
+            });
+        info!("Shared '{}' with {:?} as {}", file.name.as_ref().unwrap(), grantee, role.as_str());
+    }
+
+    permissions_all(hub, file).await
+}
+
+// Removes the permission from all the parts of a file, and returns the permissions afterwards
+pub async fn revoke_all(hub: &HubType, file: &File, permission_id: &str) -> Vec<SharedPermission> {
+    let mut revoked = false;
+
+    for file in create_file_list(hub, file).await {
+        let has_permission = permissions(hub, &file).await
+            .iter()
+            .any(|permission| permission.id.as_deref() == Some(permission_id));
+        if !has_permission {
+            continue;
+        }
+
+        hub.permissions()
+            .delete(file.id.as_ref().unwrap(), permission_id)
+            .supports_all_drives(true)
+            .add_scope(auth::scope())
+            .doit()
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed at revoking the permission '{}' of the file '{}' - {}",
+                       permission_id, file.name.as_ref().unwrap(), e);
+                auth::log_scope_hint(&e);
+                exit(misc::EXIT_CODE_024)
+            });
+        info!("Revoked the permission '{}' of '{}'", permission_id, file.name.as_ref().unwrap());
+        revoked = true;
+    }
+
+    if !revoked {
+        error!("The file '{}' does not have the permission '{}'", file.name.as_ref().unwrap(), permission_id);
+        exit(misc::EXIT_CODE_024)
+    }
+
+    permissions_all(hub, file).await
+}
@@ -1,18 +1,49 @@
+use crate::auth;
 use crate::auth::HubType;
 use crate::drive::info::FIELDS;
-use crate::drive::{delete, list, rename, set_description};
+use crate::drive::{delete, list, parity, rename, set_description};
+use crate::erasure::{ParityEncoder, ParityReader};
 use crate::misc;
 use crate::pipe_buffer::PipeBuffer;
 use age::x25519::Recipient;
-use google_drive3::api::{File, Scope};
+use google_drive3::api::File;
+use std::collections::HashMap;
 use std::io::Read;
 use std::process::exit;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct UploadResult {
+    // False if the upload failed, the parts uploaded before the failure are either deleted
+    // (and listed in `deleted_files`), or with `keep_partial` listed in `uploaded_files`
+    pub complete: bool,
     pub uploaded_files: Vec<File>,
     pub deleted_files: Vec<File>,
+    // The size of the data of a deduplicated upload, the uploaded file is only the index of the chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_size: Option<u64>,
+    // The data parts are uploaded, but the parity parts (--parity) are not
+    #[serde(default)]
+    pub parity_failed: bool,
+}
+
+// How a file is uploaded, by default the upload fails if a file with the name already exists
+#[derive(Default)]
+pub struct UploadOptions {
+    // Allow multiple files to have the same name
+    pub duplicate: bool,
+    // Replace the existing file, after the new file is uploaded successfully
+    pub replace: bool,
+    // Keep the uploaded parts, if the upload fails
+    pub keep_partial: bool,
+    pub encryption_pub_key: Option<Recipient>,
+    // The number of Reed-Solomon parity parts uploaded along with the parts (0 is none)
+    pub parity_parts: usize,
+    // Saved as properties of every part (and parity part), e.g. the tags
+    pub properties: HashMap<String, String>,
+    // The files with the name (and the parity parts of them) in the folder, if they are already known (e.g. by
+    // `push`), otherwise the folder is listed to find them. Not used by the deduplicated upload
+    pub existing_files: Option<Vec<File>>,
 }
 
 pub async fn upload<T>(
@@ -21,30 +52,39 @@ pub async fn upload<T>(
     size: usize,
     filename: String,
     parent_folder_id: Option<String>,
-    duplicate: bool,
-    replace: bool,
-    encryption_pub_key: Option<Recipient>,
+    options: &UploadOptions,
 ) -> UploadResult where T: Read + std::marker::Send {
     let mut upload_status = UploadResult {
+        complete: false,
         uploaded_files: vec![],
         deleted_files: vec![],
+        data_size: None,
+        parity_failed: false,
     };
 
     let mut filename = filename.clone();
+    let parent_folder_id = parent_folder_id.or_else(auth::default_folder);
 
-    if encryption_pub_key.is_some() {
+    if options.encryption_pub_key.is_some() {
         filename.push_str(".age")
     }
 
     let mut file_filter = Vec::new();
+    let mut parity_filter = Vec::new();
 
     // Check if there already exist files with
-    if !duplicate {
+    if !options.duplicate {
+        let files = match options.existing_files.as_ref() {
+            Some(files) => files.clone(),
+            None => list(&hub, parent_folder_id.clone()).await,
+        };
         file_filter = misc::file_filter(
             format!(r#"^{}(\.[0-9]+)?$"#, regex::escape(filename.as_ref())).as_str(),
-            &list(&hub, parent_folder_id.clone()).await,
+            &files,
         );
-        if file_filter.len() > 0 && !replace {
+        // The parity parts of the existing file are replaced as well (they don't match the new file)
+        parity_filter = misc::file_filter(&parity::parity_regex(&filename), &files);
+        if file_filter.len() > 0 && !options.replace {
             error!("The file '{}' already exist, use the replace flag (--replace) \
                     if you want to replace this file, or use the duplicate flag (--duplicate) \
                     if you don't care that multiple files have the same filename",
@@ -53,41 +93,57 @@ pub async fn upload<T>(
         }
     }
 
-    if replace {
-        for file in &file_filter {
-            upload_status.deleted_files.push(file.clone());
-            delete(hub, file).await;
-        }
-    }
+    // When replacing, the new file is uploaded under a temporary name, and the existing
+    // file is only deleted (and the new file renamed into place) if the upload succeeded
+    let replacing = options.replace && !file_filter.is_empty();
+    let upload_name = if replacing {
+        format!("{}.replace-{}", filename, chrono::Local::now().format("%Y%m%d%H%M%S"))
+    } else {
+        filename.clone()
+    };
 
     info!("Starter uploading...");
-    let mut buffer = PipeBuffer::new(
-        buffer,
-        size,
-        encryption_pub_key,
-        1024 * 1024 * 4);
+    let encoder = (options.parity_parts > 0).then(|| ParityEncoder::new(options.parity_parts, size as u64))
+        .transpose()
+        .unwrap_or_else(|e| {
+            error!("Failed at calculating the parity parts of '{}' - {}", filename, e);
+            exit(misc::EXIT_CODE_032)
+        });
+    let mut buffer = ParityReader {
+        inner: PipeBuffer::new(
+            buffer,
+            size,
+            options.encryption_pub_key.clone(),
+            1024 * 1024 * 4),
+        encoder,
+        error: None,
+    };
 
     let mut count = 0;
-    while buffer.is_there_more() {
+    let mut failed = false;
+    while buffer.inner.is_there_more() {
         let mut req = File::default();
         if parent_folder_id.is_some() {
             req.parents = Some(vec![parent_folder_id.as_ref().unwrap().to_owned()]);
         }
+        if !options.properties.is_empty() {
+            req.properties = Some(options.properties.clone());
+        }
 
-        req.name = Some(format!("{}.{count:0>3}", &filename, count = count));
+        req.name = Some(format!("{}.{count:0>3}", &upload_name, count = count));
 
         let result = hub
             .files()
             .create(req.clone())
             .supports_all_drives(true)
             .param("fields", FIELDS)
-            .add_scope(Scope::Full)
+            .add_scope(auth::scope())
             .upload_resumable(
                 &mut buffer,
                 "application/octet-stream".parse().unwrap())
             .await;
 
-        if buffer.is_there_more() {
+        if buffer.inner.is_there_more() {
             count += 1
         }
 
@@ -101,7 +157,7 @@ pub async fn upload<T>(
                     let rename_result = rename(
                         &hub,
                         uploaded_file.id.as_ref().unwrap(),
-                        filename.clone(),
+                        upload_name.clone(),
                     )
                     .await;
 
@@ -111,28 +167,28 @@ pub async fn upload<T>(
                             info!("Renamed file: '{0}.000' to '{0}'", rename_file.name.as_ref().unwrap());
                             uploaded_file.name = rename_file.name;
                         },
-                        Err(e) => error!("Failed at renaming the file '{}' - {}", &filename, e),
+                        Err(e) => error!("Failed at renaming the file '{}' - {}", &upload_name, e),
                     }
                 }
 
-                if !buffer.is_there_more() {
+                if !buffer.inner.is_there_more() {
                     let desc_result = set_description(
                         hub,
                         uploaded_file.id.as_ref().unwrap(),
-                        buffer.nulls().to_string()).await;
+                        buffer.inner.nulls().to_string()).await;
 
                     match desc_result {
                         Ok(_) => {
-                            uploaded_file.description = Some(buffer.nulls().to_string());
+                            uploaded_file.description = Some(buffer.inner.nulls().to_string());
                             info!("Set the number of concatenated nulls (0x00) \
                                    bytes to {nulls} in the description for '{filename}{suffix}'",
-                                  nulls = buffer.nulls(),
-                                  filename = filename,
+                                  nulls = buffer.inner.nulls(),
+                                  filename = upload_name,
                                   suffix = if count == 0 { "".to_owned() } else { format!(".{count:0>3}", count = count) })
                         },
                         Err(e) => warn!("Set the number of concatenated null (0x00) \
                                          bytes in the description - '{}' - {}",
-                                        filename, e)
+                                        upload_name, e)
                     }
                 }
 
@@ -144,10 +200,124 @@ pub async fn upload<T>(
             }
             Err(e) => {
                 error!("Failed at uploading '{}'", e);
+                auth::log_scope_hint(&e);
+                failed = true;
                 break;
             }
         }
     }
 
+    if !failed && replacing {
+        if let Err(err) = verify_parts(&upload_status.uploaded_files, size) {
+            error!("The uploaded file '{}' is invalid - {}", upload_name, err);
+            failed = true;
+        }
+    }
+
+    if failed {
+        if replacing {
+            error!("Failed at replacing the file '{}', the existing file is left untouched", filename);
+        }
+        if options.keep_partial {
+            warn!("The upload of '{}' is incomplete, keeping the {} uploaded part(s)",
+                  upload_name, upload_status.uploaded_files.len());
+        } else {
+            for file in upload_status.uploaded_files.drain(..) {
+                delete(hub, &file).await;
+                upload_status.deleted_files.push(file);
+            }
+        }
+        return upload_status;
+    }
+
+    if replacing {
+        file_filter.append(&mut parity_filter);
+        replace_files(hub, &mut upload_status, file_filter, &filename).await;
+    }
+
+    // The data parts are kept, but the upload is incomplete without the parity parts there was asked for
+    let parity_result = match (buffer.encoder.take(), buffer.error.take()) {
+        (_, Some(e)) => Err(e.to_string()),
+        (Some(encoder), None) => {
+            let padding = buffer.inner.nulls();
+            parity::upload_parity(hub, encoder, &filename, parent_folder_id, padding, options, &mut upload_status).await
+        }
+        (None, None) => Ok(()),
+    };
+    if let Err(e) = parity_result {
+        error!("The file '{}' is uploaded without the parity parts - {}", filename, e);
+        upload_status.parity_failed = true;
+        return upload_status;
+    }
+
+    upload_status.complete = true;
     return upload_status;
 }
+
+// Every part is uploaded with the full size (the last part is filled with 0x00)
+fn verify_parts(files: &[File], size: usize) -> Result<(), String> {
+    if files.is_empty() {
+        return Err("no files were uploaded".to_owned());
+    }
+    for file in files {
+        let file_size = file.size.as_ref().and_then(|s| s.parse::<usize>().ok());
+        if file_size != Some(size) {
+            return Err(format!("the size of '{}' is {:?} bytes, expected {} bytes",
+                               file.name.as_ref().unwrap(), file_size, size));
+        }
+    }
+    Ok(())
+}
+
+// The name of an uploaded part, after it is renamed from the temporary name into place
+fn final_name(filename: &str, part_name: &str, part_count: usize) -> String {
+    match misc::part_suffix(part_name) {
+        Some(suffix) if part_count > 1 => format!("{}{}", filename, suffix),
+        _ => filename.to_owned(),
+    }
+}
+
+async fn replace_files(hub: &HubType,
+                       upload_status: &mut UploadResult,
+                       old_files: Vec<File>,
+                       filename: &str) {
+    for file in old_files {
+        delete(hub, &file).await;
+        upload_status.deleted_files.push(file);
+    }
+
+    let part_count = upload_status.uploaded_files.len();
+    for file in upload_status.uploaded_files.iter_mut() {
+        let old_name = file.name.clone().unwrap();
+        let name = final_name(filename, &old_name, part_count);
+
+        let (_, renamed_file) = rename(hub, file.id.as_ref().unwrap(), name).await.unwrap_or_else(|e| {
+            error!("Failed at renaming the file '{}' into place - {}", old_name, e);
+            auth::log_scope_hint(&e);
+            exit(misc::EXIT_CODE_017);
+        });
+        info!("Renamed file: '{}' to '{}'", old_name, renamed_file.name.as_ref().unwrap());
+        file.name = renamed_file.name;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{final_name, verify_parts};
+    use google_drive3::api::File;
+
+    #[test]
+    fn test_010_final_name() {
+        assert_eq!("db.tar", final_name("db.tar", "db.tar.replace-20261019120000", 1));
+        assert_eq!("db.tar.001", final_name("db.tar", "db.tar.replace-20261019120000.001", 2));
+    }
+
+    #[test]
+    fn test_020_verify_parts() {
+        let part = |size: &str| File { name: Some("db.tar".to_owned()), size: Some(size.to_owned()), ..Default::default() };
+
+        assert!(verify_parts(&[part("100"), part("100")], 100).is_ok());
+        assert!(verify_parts(&[part("100"), part("40")], 100).is_err());
+        assert!(verify_parts(&[], 100).is_err());
+    }
+}
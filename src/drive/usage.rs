@@ -0,0 +1,177 @@
+use crate::auth;
+use crate::auth::HubType;
+use crate::crypto;
+use crate::drive::about::{about, get_about};
+use crate::drive::path::{tree, TreeEntry};
+use crate::misc;
+use bytesize::ByteSize;
+use google_drive3::api::AboutStorageQuota;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::exit;
+
+// The storage quota of the account in bytes, the limit is `None` if it is unlimited
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Quota {
+    pub limit: Option<u64>,
+    pub usage: u64,
+    pub usage_in_drive: u64,
+    pub usage_in_drive_trash: u64,
+    pub free: Option<u64>,
+}
+
+impl Quota {
+    fn new(storage_quota: AboutStorageQuota) -> Quota {
+        let parse = |v: Option<String>| v.and_then(|v| v.parse::<u64>().ok());
+
+        let limit = parse(storage_quota.limit);
+        let usage = parse(storage_quota.usage).unwrap_or(0);
+        Quota {
+            limit,
+            usage,
+            usage_in_drive: parse(storage_quota.usage_in_drive).unwrap_or(0),
+            usage_in_drive_trash: parse(storage_quota.usage_in_drive_trash).unwrap_or(0),
+            free: limit.map(|limit| limit.saturating_sub(usage)),
+        }
+    }
+}
+
+// The size of a folder (or of the whole tree, with the path '.') summed from the logical files in it
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct DiskUsage {
+    pub path: String,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+pub async fn quota(hub: &HubType) -> Quota {
+    Quota::new(about(hub).await.storage_quota.unwrap_or_default())
+}
+
+// The space an upload takes up, the SIZE is an estimate of the data, and encrypting
+// the data only makes it a bit bigger (the header and an authentication tag per chunk).
+// Every parity part is as big as a part (SIZE)
+pub fn required_upload_size(size: u64, encrypted: bool, parity_parts: usize) -> u64 {
+    let data_size = if encrypted && size > 0 { crypto::encrypted_size(size) } else { size };
+    data_size + parity_parts as u64 * size
+}
+
+// Fails fast if the upload does not fit in the free storage quota, if the quota
+// cannot be read (e.g. because of the scope) the upload is attempted anyway
+pub async fn check_quota(hub: &HubType, size: u64, encrypted: bool, parity_parts: usize) {
+    // The files in a shared drive do not count towards the storage quota of the user
+    if auth::shared_drive().is_some() {
+        return;
+    }
+
+    let storage_quota = match get_about(hub).await {
+        Ok(about) => about.storage_quota.unwrap_or_default(),
+        Err(e) => {
+            warn!("Skipping the storage quota check, failed at getting info about the account - {}", e);
+            return;
+        }
+    };
+
+    let quota = Quota::new(storage_quota);
+    let required = required_upload_size(size, encrypted, parity_parts);
+    debug!("The upload requires {} bytes, free storage quota: {:?} bytes", required, quota.free);
+
+    if let (Some(free), Some(limit)) = (quota.free, quota.limit) {
+        if required > free {
+            error!("Not enough free storage quota for the upload, it requires {} (at least), \
+                    but only {} of {} is free",
+                   ByteSize::b(required), ByteSize::b(free), ByteSize::b(limit));
+            exit(misc::EXIT_CODE_022)
+        }
+    }
+}
+
+// Sums the sizes of the files below every folder in the tree, deepest folders first (like `du`)
+pub fn disk_usage(entries: &[TreeEntry]) -> Vec<DiskUsage> {
+    let mut folders: BTreeMap<String, (u64, usize)> = BTreeMap::new();
+    folders.insert(".".to_owned(), (0, 0));
+
+    for entry in entries {
+        if entry.file.is_folder() {
+            folders.entry(entry.path.clone()).or_insert((0, 0));
+            continue;
+        }
+
+        // The parity parts take up space as well
+        let parity_size = entry.file.parity.iter()
+            .filter_map(|file| file.size.as_ref().and_then(|s| s.parse::<u64>().ok()))
+            .sum::<u64>();
+        let size = entry.file.size.unwrap_or(0).max(0) as u64 + parity_size;
+        let ancestors = entry.path.match_indices('/')
+            .map(|(index, _)| entry.path[..index].to_owned())
+            .chain(std::iter::once(".".to_owned()));
+        for ancestor in ancestors {
+            let usage = folders.entry(ancestor).or_insert((0, 0));
+            usage.0 += size;
+            usage.1 += 1;
+        }
+    }
+
+    let mut usages: Vec<DiskUsage> = folders.into_iter()
+        .map(|(path, (size, file_count))| DiskUsage { path, size, file_count })
+        .collect();
+    usages.sort_by(|u1, u2| {
+        let depth = |usage: &DiskUsage| if usage.path == "." { 0 } else { usage.path.matches('/').count() + 1 };
+        depth(u2).cmp(&depth(u1)).then(u1.path.cmp(&u2.path))
+    });
+    usages
+}
+
+pub async fn du(hub: &HubType, folder_id: Option<String>) -> Vec<DiskUsage> {
+    disk_usage(&tree(hub, folder_id).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disk_usage, required_upload_size, DiskUsage};
+    use crate::drive::list::{group_files, FOLDER_MIME_TYPE};
+    use crate::drive::path::TreeEntry;
+    use google_drive3::api::File;
+
+    #[test]
+    fn test_010_required_upload_size() {
+        assert_eq!(100, required_upload_size(100, false, 0));
+        // The encryption overhead is added, not a whole extra part
+        assert_eq!(1024 * 1024 + 256 + 16 * 17, required_upload_size(1024 * 1024, true, 0));
+        assert_eq!(0, required_upload_size(0, true, 0));
+        // The parity parts use the quota as well
+        assert_eq!(300, required_upload_size(100, false, 2));
+    }
+
+    #[test]
+    fn test_020_disk_usage() {
+        let entry = |path: &str, depth: usize, size: Option<&str>| TreeEntry {
+            path: path.to_owned(),
+            depth,
+            file: group_files(vec![File {
+                id: Some(path.to_owned()),
+                name: Some(path.rsplit('/').next().unwrap().to_owned()),
+                mime_type: Some(if size.is_some() { "application/octet-stream" } else { FOLDER_MIME_TYPE }.to_owned()),
+                size: size.map(|s| s.to_owned()),
+                ..Default::default()
+            }]).remove(0),
+        };
+
+        let usages = disk_usage(&[
+            entry("backups", 0, None),
+            entry("backups/db", 1, None),
+            entry("backups/db/1.tar", 2, Some("100")),
+            entry("backups/notes.txt", 1, Some("10")),
+            entry("empty", 0, None),
+            entry("top.txt", 0, Some("1")),
+        ]);
+
+        let usage = |path: &str, size: u64, file_count: usize| DiskUsage { path: path.to_owned(), size, file_count };
+        assert_eq!(vec![
+            usage("backups/db", 100, 1),
+            usage("backups", 110, 2),
+            usage("empty", 0, 0),
+            usage(".", 111, 3),
+        ], usages);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::auth::HubType;
+use crate::catalog::CatalogRecord;
+use crate::crypto;
+use crate::drive::dedup;
+use crate::drive::dedup::ChunkIndex;
+use crate::drive::download::download_parts;
+use crate::drive::list::{create_file_list_with_trashed, list_with_trashed};
+use crate::drive::parity;
+use crate::erasure::ParityInfo;
+use crate::misc;
+use google_drive3::api::File;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+#[derive(Serialize, Debug)]
+pub struct PartCheck {
+    pub name: String,
+    pub id: Option<String>,
+    pub size: Option<u64>,
+    pub md5: Option<String>,
+    pub passed: bool,
+    pub problems: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct VerifyReport {
+    pub name: String,
+    pub passed: bool,
+    pub parts: Vec<PartCheck>,
+    // `None` if the decryption was not checked
+    pub decrypted: Option<bool>,
+    pub problems: Vec<String>,
+}
+
+fn file_size(file: &File) -> Option<u64> {
+    file.size.as_ref().and_then(|size| size.parse::<u64>().ok())
+}
+
+// The name of the part with the index, when the file is split into `part_count` parts
+fn part_name(name: &str, index: usize, part_count: usize) -> String {
+    if part_count == 1 {
+        name.to_owned()
+    } else {
+        format!("{}.{:0>3}", name, index)
+    }
+}
+
+// The record of the upload in the catalog, found by the IDs of the parts (the newest record, if there are multiple)
+fn find_record<'a>(records: &'a [CatalogRecord], files: &[File]) -> Option<&'a CatalogRecord> {
+    records.iter()
+        .rev()
+        .filter(|record| !record.dedup)
+        .find(|record| record.parts.iter().any(|part| files.iter().any(|file| file.id.as_deref() == Some(part.id.as_str()))))
+}
+
+// Checks that all the parts exist (and are not trashed), have the same size, and that the padding in the description
+// of the last part is valid. The MD5 checksums of the parts are checked against the parity info (of the parity parts),
+// or else against the MD5 checksums recorded in the catalog when the file was uploaded
+fn check_parts(name: &str, files: &[File], parity_info: Option<&ParityInfo>, record: Option<&CatalogRecord>) -> Vec<PartCheck> {
+    let index_of = |file: &File| match parity_info {
+        Some(parity_info) => parity::part_index(parity_info, file.name.as_ref().unwrap()),
+        None => match misc::part_suffix(file.name.as_ref().unwrap()) {
+            Some(suffix) => suffix[1..].parse::<usize>().ok(),
+            None => Some(0),
+        },
+    };
+
+    let part_count = files.iter()
+        .filter_map(index_of)
+        .map(|index| index + 1)
+        .chain(parity_info.map(|parity_info| parity_info.data_parts))
+        .chain(record.map(|record| record.parts.len()))
+        .max()
+        .unwrap_or(0);
+    let part_size = parity_info
+        .map(|parity_info| parity_info.part_size)
+        .or_else(|| files.first().and_then(file_size));
+
+    let mut checks: Vec<PartCheck> = (0..part_count)
+        .map(|index| PartCheck {
+            name: part_name(name, index, part_count),
+            id: None,
+            size: None,
+            md5: None,
+            passed: false,
+            problems: vec!["the part is missing".to_owned()],
+        })
+        .collect();
+
+    for file in files {
+        let mut check = PartCheck {
+            name: file.name.clone().unwrap(),
+            id: file.id.clone(),
+            size: file_size(file),
+            md5: file.md5_checksum.clone(),
+            passed: false,
+            problems: Vec::new(),
+        };
+
+        let index = match index_of(file) {
+            Some(index) => index,
+            None => {
+                check.problems.push("the part is not covered by the parity info".to_owned());
+                checks.push(check);
+                continue;
+            }
+        };
+
+        if file.trashed.unwrap_or(false) {
+            check.problems.push("the part is trashed".to_owned());
+        }
+        if check.size != part_size {
+            check.problems.push(format!("the size is {} bytes, expected {} bytes",
+                                        check.size.unwrap_or(0), part_size.unwrap_or(0)));
+        }
+        if let Some(parity_info) = parity_info {
+            if check.md5.as_ref() != parity_info.md5.get(index) {
+                check.problems.push("the MD5 checksum does not match the parity info".to_owned());
+            }
+        } else if let Some(record) = record {
+            let recorded = record.parts.iter().find(|part| check.id.as_deref() == Some(part.id.as_str()));
+            match recorded.and_then(|part| part.md5.as_ref()) {
+                Some(md5) if check.md5.as_ref() != Some(md5) => {
+                    check.problems.push("the MD5 checksum does not match the catalog".to_owned())
+                }
+                Some(_) => {}
+                None => check.problems.push("the part is not in the catalog record of the upload".to_owned()),
+            }
+        }
+
+        // The number of filler bytes (0x00) is the first line of the description of the last part
+        if index + 1 == part_count {
+            match misc::padding_from_description(&file.description) {
+                None => check.problems.push("the description has no padding".to_owned()),
+                Some(padding) if padding < 0 || Some(padding as u64) > check.size => {
+                    check.problems.push(format!("the padding ({} bytes) is bigger than the part", padding))
+                }
+                Some(padding) => {
+                    if let Some(parity_info) = parity_info {
+                        if padding as u64 != parity_info.padding {
+                            check.problems.push(format!("the padding ({} bytes) does not match the parity info ({} bytes)",
+                                                        padding, parity_info.padding));
+                        }
+                    }
+                }
+            }
+        }
+
+        check.passed = check.problems.is_empty();
+        checks[index] = check;
+    }
+
+    checks
+}
+
+// Checks that every chunk listed in the INDEX exists in the chunk folder (and is not trashed), and has the size of
+// the data in the index (an encrypted chunk is bigger by the age header and tags). A chunk used multiple times is
+// only checked once
+fn check_chunks(index: &ChunkIndex, chunk_files: &[File]) -> Vec<PartCheck> {
+    let files: HashMap<&str, &File> = chunk_files.iter()
+        .map(|file| (file.id.as_deref().unwrap(), file))
+        .collect();
+
+    let mut checked = HashSet::new();
+    index.chunks.iter()
+        .filter(|chunk| checked.insert(chunk.id.as_str()))
+        .map(|chunk| {
+            let file = match files.get(chunk.id.as_str()) {
+                Some(file) => file,
+                None => return PartCheck {
+                    name: chunk.hash.clone(),
+                    id: Some(chunk.id.clone()),
+                    size: None,
+                    md5: None,
+                    passed: false,
+                    problems: vec!["the chunk is missing".to_owned()],
+                },
+            };
+
+            let mut check = PartCheck {
+                name: chunk.hash.clone(),
+                id: file.id.clone(),
+                size: file_size(file),
+                md5: file.md5_checksum.clone(),
+                passed: false,
+                problems: Vec::new(),
+            };
+            if file.trashed.unwrap_or(false) {
+                check.problems.push("the chunk is trashed".to_owned());
+            }
+            if file.name.as_deref().map(|name| name.trim_end_matches(".age")) != Some(chunk.hash.as_str()) {
+                check.problems.push("the name of the chunk does not match the index".to_owned());
+            }
+            let size = check.size.unwrap_or(0);
+            if index.encrypted && (size <= chunk.size || size > crypto::encrypted_size(chunk.size)) {
+                check.problems.push(format!("the size is {} bytes, expected {} bytes encrypted", size, chunk.size));
+            } else if !index.encrypted && size != chunk.size {
+                check.problems.push(format!("the size is {} bytes, expected {} bytes", size, chunk.size));
+            }
+
+            check.passed = check.problems.is_empty();
+            check
+        })
+        .collect()
+}
+
+// Verifies the chunks of a deduplicated upload, with `decrypt` the chunks are downloaded and decrypted
+async fn verify_dedup(hub: &HubType, info: &File, decrypt: bool) -> VerifyReport {
+    let index = dedup::load_index(hub, info).await;
+    let chunk_files = list_with_trashed(hub, Some(index.chunk_folder_id.clone())).await;
+    let parts = check_chunks(&index, &chunk_files);
+
+    let mut report = VerifyReport {
+        name: info.name.clone().unwrap(),
+        passed: parts.iter().all(|part| part.passed),
+        parts,
+        decrypted: None,
+        problems: Vec::new(),
+    };
+    if index.chunks.iter().map(|chunk| chunk.size).sum::<u64>() != index.size {
+        report.passed = false;
+        report.problems.push(format!("the chunks do not add up to the size of the file ({} bytes)", index.size));
+    }
+
+    if decrypt {
+        if !index.encrypted {
+            warn!("The file '{}' is not encrypted, the decryption is not checked", report.name);
+        } else if !report.passed {
+            report.problems.push("the decryption is not checked, because the chunks are damaged".to_owned());
+        } else {
+            // A chunk there cannot be decrypted stops the restore with an error
+            info!("Checking the decryption of '{}'", report.name);
+            dedup::restore(hub, info, &mut io::sink()).await;
+            report.decrypted = Some(true);
+        }
+    }
+
+    report
+}
+
+// Verifies the stored parts of a file against the parity info or the catalog RECORDS, with `decrypt` the encrypted
+// data is decrypted (without writing it anywhere) to check the authentication tags (MAC) of it
+pub async fn verify(hub: &HubType, info: &File, decrypt: bool, records: &[CatalogRecord]) -> VerifyReport {
+    if dedup::is_index(info) {
+        return verify_dedup(hub, info, decrypt).await;
+    }
+
+    // The trashed parts are listed as well, so they are reported as trashed instead of missing
+    let files = create_file_list_with_trashed(hub, info).await;
+    let parity = parity::find_parity(hub, info, &files).await;
+    let name = parity::logical_name(info.name.as_ref().unwrap()).to_owned();
+    let record = find_record(records, &files);
+    if parity.is_none() && record.is_none() {
+        warn!("The file '{}' has no parity parts and is not in the catalog, the MD5 checksums are not checked", name);
+    }
+
+    let parts = check_parts(&name, &files, parity.as_ref().map(|(_, parity_info, _)| parity_info), record);
+    let mut report = VerifyReport {
+        passed: parts.iter().all(|part| part.passed),
+        name,
+        parts,
+        decrypted: None,
+        problems: Vec::new(),
+    };
+
+    if decrypt {
+        if !report.name.ends_with(".age") {
+            warn!("The file '{}' is not encrypted, the decryption is not checked", report.name);
+        } else if !report.passed {
+            report.problems.push("the decryption is not checked, because the parts are damaged".to_owned());
+        } else {
+            info!("Checking the decryption of '{}'", report.name);
+            let mut verifier = crypto::DecryptVerifier::new(crypto::load_private_key(None));
+            download_parts(hub, files, Some(&mut verifier), None).await;
+            match verifier.finish() {
+                Ok(size) => {
+                    debug!("Decrypted {} bytes of '{}'", size, report.name);
+                    report.decrypted = Some(true);
+                }
+                Err(e) => {
+                    report.decrypted = Some(false);
+                    report.passed = false;
+                    report.problems.push(format!("the decryption failed - {}", e));
+                }
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_chunks, check_parts, find_record};
+    use crate::catalog::{CatalogPart, CatalogRecord};
+    use crate::drive::dedup::{ChunkIndex, ChunkRef};
+    use crate::erasure::ParityInfo;
+    use google_drive3::api::File;
+
+    fn part(name: &str, size: u64, md5: &str, description: Option<&str>) -> File {
+        File {
+            name: Some(name.to_owned()),
+            id: Some(format!("id-{}", name)),
+            size: Some(size.to_string()),
+            md5_checksum: Some(md5.to_owned()),
+            description: description.map(|d| d.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_010_check_parts() {
+        let files = vec![part("db.tar.000", 100, "a", None), part("db.tar.001", 100, "b", Some("40\nnightly"))];
+        let checks = check_parts("db.tar", &files, None, None);
+        assert!(checks.iter().all(|check| check.passed));
+
+        // A part is missing in the middle, and the last part is too small
+        let files = vec![part("db.tar.000", 100, "a", None), part("db.tar.002", 60, "c", Some("40"))];
+        let checks = check_parts("db.tar", &files, None, None);
+        assert_eq!(vec![true, false, false], checks.iter().map(|check| check.passed).collect::<Vec<bool>>());
+        assert_eq!("db.tar.001", checks[1].name);
+
+        // The padding is bigger than the part
+        let files = vec![part("db.tar", 100, "a", Some("140"))];
+        assert!(!check_parts("db.tar", &files, None, None)[0].passed);
+    }
+
+    #[test]
+    fn test_020_check_parts_with_parity_info() {
+        let parity_info = ParityInfo {
+            version: 1,
+            data_shards: 254,
+            data_parts: 3,
+            parity_parts: 2,
+            part_size: 100,
+            padding: 40,
+            md5: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+        };
+
+        // The last part is missing, which is only known from the parity info
+        let files = vec![part("db.tar.000", 100, "a", None), part("db.tar.001", 100, "x", None)];
+        let checks = check_parts("db.tar", &files, Some(&parity_info), None);
+        assert_eq!(3, checks.len());
+        assert!(checks[0].passed);
+        assert_eq!(vec!["the MD5 checksum does not match the parity info".to_owned()], checks[1].problems);
+        assert!(!checks[2].passed);
+    }
+
+    #[test]
+    fn test_030_check_parts_with_catalog() {
+        let record = CatalogRecord {
+            name: "db.tar".to_owned(),
+            folder_id: None,
+            size: 160,
+            parts: ["a", "b"].iter().enumerate()
+                .map(|(index, md5)| CatalogPart {
+                    id: format!("id-db.tar.{:0>3}", index),
+                    name: format!("db.tar.{:0>3}", index),
+                    size: 100,
+                    md5: Some(md5.to_string()),
+                })
+                .collect(),
+            encrypted: false,
+            dedup: false,
+            uploaded: chrono::Utc::now(),
+            host: "host".to_owned(),
+            tags: Default::default(),
+        };
+        let records = vec![record];
+
+        let files = vec![part("db.tar.000", 100, "a", None), part("db.tar.001", 100, "x", Some("40"))];
+        let record = find_record(&records, &files);
+        assert!(record.is_some());
+        let checks = check_parts("db.tar", &files, None, record);
+        assert!(checks[0].passed);
+        assert_eq!(vec!["the MD5 checksum does not match the catalog".to_owned()], checks[1].problems);
+
+        // A trashed part is reported as trashed, and the last part is missing, which is only known from the catalog
+        let mut trashed = part("db.tar.000", 100, "a", None);
+        trashed.trashed = Some(true);
+        let checks = check_parts("db.tar", &[trashed], None, find_record(&records, &files));
+        assert_eq!(2, checks.len());
+        assert_eq!(vec!["the part is trashed".to_owned()], checks[0].problems);
+        assert_eq!(vec!["the part is missing".to_owned()], checks[1].problems);
+    }
+
+    #[test]
+    fn test_040_check_chunks() {
+        let chunk = |hash: &str, size: u64| ChunkRef { hash: hash.to_owned(), size, id: format!("id-{}", hash) };
+        let index = ChunkIndex {
+            version: 1,
+            size: 300,
+            encrypted: false,
+            chunk_folder_id: "chunks".to_owned(),
+            chunks: vec![chunk("a", 100), chunk("b", 100), chunk("a", 100), chunk("c", 0)],
+        };
+
+        let mut trashed = part("b", 100, "m", None);
+        trashed.trashed = Some(true);
+        let checks = check_chunks(&index, &[part("a", 100, "m", None), trashed]);
+        assert_eq!(3, checks.len());
+        assert!(checks[0].passed);
+        assert_eq!(vec!["the chunk is trashed".to_owned()], checks[1].problems);
+        assert_eq!(vec!["the chunk is missing".to_owned()], checks[2].problems);
+
+        // An encrypted chunk is bigger than the data, but not by more than the age overhead
+        let index = ChunkIndex { encrypted: true, chunks: vec![chunk("a", 100), chunk("b", 100)], ..index };
+        let encrypted = |hash: &str, size: u64| File { id: Some(format!("id-{}", hash)), ..part(&format!("{}.age", hash), size, "m", None) };
+        let checks = check_chunks(&index, &[encrypted("a", 300), encrypted("b", 100)]);
+        assert!(checks[0].passed);
+        assert!(!checks[1].passed);
+    }
+}
@@ -0,0 +1,291 @@
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// The codec always uses the most data shards possible (256 - the parity parts), and the data parts there does
+// not exist are all zeros, so the number of data parts does not have to be known before the data is uploaded
+const MAX_SHARDS: usize = 256;
+// The parts are rebuilt a block at a time, because all the data shards are needed for every block
+const BLOCK_SIZE: usize = 64 * 1024;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Describes the parity parts of an upload, it is saved in the description of every parity part
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ParityInfo {
+    pub version: u32,
+    pub data_shards: usize,
+    pub data_parts: usize,
+    pub parity_parts: usize,
+    pub part_size: u64,
+    // The number of filler bytes (0x00) at the end of the last data part
+    pub padding: u64,
+    // The MD5 checksums of the data parts, to find the damaged parts
+    pub md5: Vec<String>,
+}
+
+// A file in the temporary folder, there is deleted when it is dropped
+pub struct TempFile {
+    path: PathBuf,
+    pub file: fs::File,
+}
+
+impl TempFile {
+    pub fn new() -> io::Result<TempFile> {
+        let path = std::env::temp_dir().join(format!(
+            "pipe2drive-{}-{}.tmp",
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let file = fs::OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(TempFile { path, file })
+    }
+
+    pub fn rewind(&mut self) -> io::Result<&mut fs::File> {
+        self.file.seek(SeekFrom::Start(0))?;
+        Ok(&mut self.file)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn codec(data_shards: usize, parity_parts: usize) -> io::Result<ReedSolomon> {
+    ReedSolomon::new(data_shards, parity_parts)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?}", e)))
+}
+
+pub fn max_data_parts(parity_parts: usize) -> usize {
+    MAX_SHARDS.saturating_sub(parity_parts)
+}
+
+// Calculates the parity parts from the data parts while they are uploaded, the parity parts are written to temporary
+// files (instead of being kept in memory), because every parity part is as big as a data part
+pub struct ParityEncoder {
+    codec: ReedSolomon,
+    part_size: u64,
+    parity: Vec<TempFile>,
+    position: u64,
+    md5: Vec<String>,
+    md5_context: md5::Context,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl ParityEncoder {
+    pub fn new(parity_parts: usize, part_size: u64) -> io::Result<ParityEncoder> {
+        ParityEncoder::with_data_shards(max_data_parts(parity_parts), parity_parts, part_size)
+    }
+
+    fn with_data_shards(data_shards: usize, parity_parts: usize, part_size: u64) -> io::Result<ParityEncoder> {
+        Ok(ParityEncoder {
+            codec: codec(data_shards, parity_parts)?,
+            part_size,
+            parity: (0..parity_parts).map(|_| TempFile::new()).collect::<io::Result<Vec<TempFile>>>()?,
+            position: 0,
+            md5: Vec::new(),
+            md5_context: md5::Context::new(),
+            buffers: vec![Vec::new(); parity_parts],
+        })
+    }
+
+    // The data has to be the parts in order, and every part has to be exactly the part size
+    pub fn update(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let part = (self.position / self.part_size) as usize;
+            let offset = self.position % self.part_size;
+            if part >= self.codec.data_shard_count() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!(
+                    "only {} parts can be protected by {} parity parts", self.codec.data_shard_count(), self.parity.len())));
+            }
+
+            let len = cmp::min(data.len() as u64, self.part_size - offset) as usize;
+            let (slice, rest) = data.split_at(len);
+
+            // The first data part initializes the parity parts, the rest are added to them
+            for (buffer, parity) in self.buffers.iter_mut().zip(self.parity.iter_mut()) {
+                buffer.resize(len, 0);
+                if part > 0 {
+                    parity.file.seek(SeekFrom::Start(offset))?;
+                    parity.file.read_exact(buffer)?;
+                }
+            }
+            self.codec.encode_single_sep(part, slice, &mut self.buffers)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?}", e)))?;
+            for (buffer, parity) in self.buffers.iter().zip(self.parity.iter_mut()) {
+                parity.file.seek(SeekFrom::Start(offset))?;
+                parity.file.write_all(buffer)?;
+            }
+
+            self.md5_context.consume(slice);
+            self.position += len as u64;
+            if self.position.is_multiple_of(self.part_size) {
+                let context = mem::replace(&mut self.md5_context, md5::Context::new());
+                self.md5.push(format!("{:x}", context.finalize()));
+            }
+            data = rest;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<(ParityInfo, Vec<TempFile>)> {
+        if !self.position.is_multiple_of(self.part_size) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the last part is incomplete"));
+        }
+
+        let info = ParityInfo {
+            version: 1,
+            data_shards: self.codec.data_shard_count(),
+            data_parts: self.md5.len(),
+            parity_parts: self.parity.len(),
+            part_size: self.part_size,
+            padding: 0,
+            md5: self.md5,
+        };
+        Ok((info, self.parity))
+    }
+}
+
+// Passes the data through to the parity encoder while it is read, if the data cannot be protected by the parity
+// parts (too many parts), the encoder is dropped and the error is kept, but the data is still read
+pub struct ParityReader<R> {
+    pub inner: R,
+    pub encoder: Option<ParityEncoder>,
+    pub error: Option<io::Error>,
+}
+
+impl<R: Read> Read for ParityReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buffer)?;
+        if let Some(encoder) = self.encoder.as_mut() {
+            if let Err(e) = encoder.update(&buffer[..len]) {
+                self.error = Some(e);
+                self.encoder = None;
+            }
+        }
+        Ok(len)
+    }
+}
+
+impl<R: Seek> Seek for ParityReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+// Rebuilds the missing data parts (`None`) from the parity parts (`None` if missing), a block at a time.
+// Returns the rebuilt data parts, in the same order as the missing parts
+pub fn reconstruct<R: Read>(info: &ParityInfo, parts: &mut [Option<R>], parity: &mut [Option<R>]) -> io::Result<Vec<TempFile>> {
+    if info.data_shards + info.parity_parts > MAX_SHARDS || parts.len() > info.data_shards || parts.len() != info.data_parts || parity.len() != info.parity_parts {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "the parity info does not match the parts"));
+    }
+    let codec = codec(info.data_shards, info.parity_parts)?;
+
+    let missing: Vec<usize> = (0..parts.len()).filter(|index| parts[*index].is_none()).collect();
+    let mut rebuilt = missing.iter().map(|_| TempFile::new()).collect::<io::Result<Vec<TempFile>>>()?;
+
+    let mut shards: Vec<(Vec<u8>, bool)> = vec![(Vec::new(), true); info.data_shards + info.parity_parts];
+    let mut offset = 0;
+    while offset < info.part_size {
+        let len = cmp::min(BLOCK_SIZE as u64, info.part_size - offset) as usize;
+
+        // The data shards after the data parts are all zeros
+        for (index, shard) in shards.iter_mut().enumerate() {
+            shard.0.resize(len, 0);
+            let reader = if index < info.data_shards {
+                match parts.get_mut(index) {
+                    Some(part) => part.as_mut(),
+                    None => {
+                        shard.0.fill(0);
+                        shard.1 = true;
+                        continue;
+                    }
+                }
+            } else {
+                parity[index - info.data_shards].as_mut()
+            };
+
+            shard.1 = reader.is_some();
+            if let Some(reader) = reader {
+                reader.read_exact(&mut shard.0)?;
+            }
+        }
+
+        codec.reconstruct_data(&mut shards)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+        for (index, temp_file) in missing.iter().zip(rebuilt.iter_mut()) {
+            temp_file.file.write_all(&shards[*index].0)?;
+        }
+
+        offset += len as u64;
+    }
+
+    Ok(rebuilt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reconstruct, ParityEncoder};
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_010_rebuild_missing_parts() {
+        let part_size = 70 * 1024 + 7;
+        let parts: Vec<Vec<u8>> = (0..5u8)
+            .map(|part| (0..part_size).map(|index| (index as u8).wrapping_mul(31).wrapping_add(part)).collect())
+            .collect();
+
+        // Fewer data shards than an upload uses, because the matrices are slow to build in a debug build
+        let mut encoder = ParityEncoder::with_data_shards(16, 2, part_size as u64).unwrap();
+        // The data is read in pieces of any size
+        for piece in parts.concat().chunks(10_000) {
+            encoder.update(piece).unwrap();
+        }
+        let (info, mut parity_files) = encoder.finish().unwrap();
+        assert_eq!(5, info.data_parts);
+        assert_eq!(format!("{:x}", md5::compute(&parts[3])), info.md5[3]);
+
+        let parity: Vec<Vec<u8>> = parity_files.iter_mut().map(|temp_file| {
+            let mut data = Vec::new();
+            temp_file.rewind().unwrap().read_to_end(&mut data).unwrap();
+            data
+        }).collect();
+
+        // Two data parts are missing
+        let mut readers: Vec<Option<Cursor<Vec<u8>>>> = parts.iter().map(|part| Some(Cursor::new(part.clone()))).collect();
+        readers[1] = None;
+        readers[4] = None;
+        let mut parity_readers: Vec<Option<Cursor<Vec<u8>>>> = parity.iter().map(|part| Some(Cursor::new(part.clone()))).collect();
+
+        let mut rebuilt = reconstruct(&info, &mut readers, &mut parity_readers).unwrap();
+        for (index, temp_file) in [1, 4].iter().zip(rebuilt.iter_mut()) {
+            let mut data = Vec::new();
+            temp_file.rewind().unwrap().read_to_end(&mut data).unwrap();
+            assert_eq!(parts[*index], data);
+        }
+
+        // A data part and a parity part are missing
+        let mut readers: Vec<Option<Cursor<Vec<u8>>>> = parts.iter().map(|part| Some(Cursor::new(part.clone()))).collect();
+        readers[0] = None;
+        let mut parity_readers: Vec<Option<Cursor<Vec<u8>>>> = vec![None, Some(Cursor::new(parity[1].clone()))];
+        let mut rebuilt = reconstruct(&info, &mut readers, &mut parity_readers).unwrap();
+        let mut data = Vec::new();
+        rebuilt[0].rewind().unwrap().read_to_end(&mut data).unwrap();
+        assert_eq!(parts[0], data);
+
+        // Three parts are missing, which is one more than there are parity parts
+        let mut readers: Vec<Option<Cursor<Vec<u8>>>> = parts.iter().map(|part| Some(Cursor::new(part.clone()))).collect();
+        readers[0] = None;
+        readers[2] = None;
+        let mut parity_readers: Vec<Option<Cursor<Vec<u8>>>> = vec![None, Some(Cursor::new(parity[1].clone()))];
+        assert!(reconstruct(&info, &mut readers, &mut parity_readers).is_err());
+    }
+}
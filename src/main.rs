@@ -9,20 +9,28 @@ extern crate regex;
 extern crate prettytable;
 extern crate ringbuf;
 
-use arguments::ArgLogLevel;
+use arguments::{ArgAuthFlow, ArgLogLevel, ArgScope, ArgShareRole};
 use pipe_buffer::TestBuffer;
 mod arguments;
 mod auth;
+mod catalog;
 mod crypto;
 mod drive;
+mod erasure;
 mod logger;
 mod misc;
 mod pipe_buffer;
+mod profile;
+mod retention;
+mod tar_stream;
 
 use crate::misc::{parse_data_size, StdinWrapperWithSendSupport};
 use log::Level;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::Path;
 use std::process::exit;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 
 
@@ -42,26 +50,262 @@ async fn main() {
 
     let json_output: bool = args.json;
 
+    profile::init(args.profile);
+    let profile_config = profile::load_config();
+
+    let auth_flow = args.auth_flow.unwrap_or_else(|| {
+        profile_config.auth_flow.as_ref().map_or(ArgAuthFlow::Redirect, |flow| {
+            ArgAuthFlow::from_str(flow, true).unwrap_or_else(|err| {
+                error!("The auth-flow '{}' in the profile config is invalid - {}", flow, err);
+                exit(misc::EXIT_CODE_015)
+            })
+        })
+    });
+    let auth_flow = match auth_flow {
+        ArgAuthFlow::Redirect => auth::AuthFlow::Redirect,
+        ArgAuthFlow::Interactive => auth::AuthFlow::Interactive,
+        ArgAuthFlow::Device => auth::AuthFlow::Device,
+    };
+
+    let scope = args.scope.unwrap_or_else(|| {
+        profile_config.scope.as_ref().map_or(ArgScope::Full, |scope| {
+            ArgScope::from_str(scope, true).unwrap_or_else(|err| {
+                error!("The scope '{}' in the profile config is invalid - {}", scope, err);
+                exit(misc::EXIT_CODE_015)
+            })
+        })
+    });
+    auth::init_scope(match scope {
+        ArgScope::Full => auth::AccessScope::Full,
+        ArgScope::File => auth::AccessScope::File,
+        ArgScope::Appdata => auth::AccessScope::Appdata,
+    });
+
+    let service_account = args.service_account.or(profile_config.service_account);
+
     let hub = auth::auth(
         args.secret,
-        args.token,
+        args.token.clone(),
+        auth_flow,
+        service_account.clone(),
+        args.impersonate.or(profile_config.impersonate),
     );
 
     match args.command {
+        arguments::Commands::Delete(delete) => {
+            let hub_tmp = hub.await;
+            let info = drive::resolve(&hub_tmp, &delete.file).await;
+            let files = drive::delete_all(&hub_tmp, &info, delete.trash).await;
+            misc::print_list(files, json_output);
+            exit(0);
+        },
+        arguments::Commands::Prune(prune) => {
+            let template = parse_name_template(&prune.name);
+            let keep_policy = parse_keep_policy(&prune.keep);
+
+            let hub_tmp = hub.await;
+            let folder = drive::resolve_folder(&hub_tmp, prune.folder).await;
+            let files = drive::prune(&hub_tmp, folder, &template, &keep_policy, prune.dry_run, prune.trash).await;
+            misc::print_logical_list(files, json_output);
+            exit(0);
+        },
+        arguments::Commands::Gc(gc) => {
+            let hub_tmp = hub.await;
+            let chunk_folder = drive::resolve_folder(&hub_tmp, Some(gc.chunk_folder)).await.unwrap();
+            misc::print_list(drive::gc(&hub_tmp, &chunk_folder, gc.dry_run, gc.trash).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Mv(mv) => {
+            let hub_tmp = hub.await;
+            let file = drive::resolve(&hub_tmp, &mv.file).await;
+            let folder = drive::resolve_folder(&hub_tmp, Some(mv.folder)).await.unwrap();
+            misc::print_list(drive::move_all(&hub_tmp, &file, &folder, mv.duplicate).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Cp(cp) => {
+            if cp.folder.is_none() && cp.name.is_none() {
+                error!("The copy needs a new name (--name) or another folder (--folder), \
+                        otherwise multiple files will have the same name");
+                exit(misc::EXIT_CODE_025)
+            }
+
+            let hub_tmp = hub.await;
+            let file = drive::resolve(&hub_tmp, &cp.file).await;
+            let folder = drive::resolve_folder(&hub_tmp, cp.folder).await;
+            misc::print_list(drive::copy_all(&hub_tmp, &file, folder, cp.name.as_deref(), cp.duplicate).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Trash(trash) => {
+            let hub_tmp = hub.await;
+            let file = drive::resolve(&hub_tmp, &trash.file).await;
+            misc::print_list(drive::set_trashed_all(&hub_tmp, &file, true).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Untrash(untrash) => {
+            let hub_tmp = hub.await;
+            let file = drive::info(&hub_tmp, &untrash.file).await;
+            misc::print_list(drive::set_trashed_all(&hub_tmp, &file, false).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Rename(rename) => {
+            let hub_tmp = hub.await;
+            let info = drive::resolve(&hub_tmp, &rename.file).await;
+            let files = drive::rename_all(&hub_tmp, &info, &rename.name).await;
+            misc::print_list(files, json_output);
+            exit(0);
+        },
+        arguments::Commands::Describe(describe) => {
+            let hub_tmp = hub.await;
+            let info = drive::resolve(&hub_tmp, &describe.file).await;
+            let files = drive::set_description_all(&hub_tmp, &info, describe.description.as_deref()).await;
+            misc::print_list(files, json_output);
+            exit(0);
+        },
+        arguments::Commands::Share(share_command) => {
+            let hub_tmp = hub.await;
+            let permissions = match share_command.command {
+                arguments::ShareCommands::Add(add) => {
+                    let file = drive::resolve(&hub_tmp, &add.file).await;
+                    let role = match add.role {
+                        ArgShareRole::Reader => drive::ShareRole::Reader,
+                        ArgShareRole::Commenter => drive::ShareRole::Commenter,
+                        ArgShareRole::Writer => drive::ShareRole::Writer,
+                    };
+                    let grantee = match add.grantee {
+                        arguments::ShareGrantee { user: Some(email), .. } => drive::Grantee::User(email),
+                        arguments::ShareGrantee { group: Some(email), .. } => drive::Grantee::Group(email),
+                        arguments::ShareGrantee { domain: Some(domain), .. } => drive::Grantee::Domain(domain),
+                        _ => drive::Grantee::Anyone,
+                    };
+                    drive::share_all(&hub_tmp, &file, &grantee, role, add.notify).await
+                },
+                arguments::ShareCommands::List(list) => {
+                    let file = drive::resolve(&hub_tmp, &list.file).await;
+                    drive::permissions_all(&hub_tmp, &file).await
+                },
+                arguments::ShareCommands::Revoke(revoke) => {
+                    let file = drive::resolve(&hub_tmp, &revoke.file).await;
+                    drive::revoke_all(&hub_tmp, &file, &revoke.permission).await
+                },
+            };
+            misc::print_permissions(permissions, json_output);
+            exit(0);
+        },
+        arguments::Commands::Drives(drives_command) => {
+            match drives_command.command {
+                arguments::DrivesCommands::List => {
+                    misc::print_drives(drive::list_drives(&hub.await).await, json_output);
+                },
+            }
+            exit(0);
+        },
+        arguments::Commands::Catalog(catalog_command) => {
+            match catalog_command.command {
+                arguments::CatalogCommands::Search(search) => {
+                    let filter = catalog::CatalogFilter {
+                        name: search.name_glob.as_deref().map(drive::glob_to_regex),
+                        host: search.host,
+                        after: search.after.as_deref().map(misc::parse_time),
+                        before: search.before.as_deref().map(misc::parse_time),
+                        tags: parse_tags(&search.tag),
+                    };
+                    let path = misc::config_file(search.catalog, catalog::CATALOG_FILE);
+                    let records = catalog::load(&path).unwrap_or_else(|e| {
+                        error!("Failed at reading the catalog '{}' - {}", path.to_string_lossy(), e);
+                        exit(misc::EXIT_CODE_031)
+                    });
+                    misc::print_catalog(catalog::search(records, &filter), json_output);
+                },
+            }
+            exit(0);
+        },
+        arguments::Commands::Auth(auth_command) => {
+            match auth_command.command {
+                arguments::AuthCommands::Login => {
+                    let status = auth::status(&hub.await).await;
+                    misc::print_auth_status(&status, json_output);
+                },
+                arguments::AuthCommands::Status => {
+                    if !auth::has_credentials(args.token, &service_account) {
+                        error!("Not logged in, use 'pipe2drive auth login' to log in");
+                        exit(misc::EXIT_CODE_016)
+                    }
+                    let status = auth::status(&hub.await).await;
+                    misc::print_auth_status(&status, json_output);
+                },
+                arguments::AuthCommands::Logout => {
+                    auth::logout(args.token).await;
+                },
+            }
+            exit(0);
+        },
         arguments::Commands::Info(info) => {
-            let info = drive::info(&hub.await, info.id.as_str()).await;
+            let info = drive::resolve(&hub.await, info.id.as_str()).await;
             misc::print_info(&info, json_output);
             exit(0);
         },
         arguments::Commands::List(list) => {
-            let files = drive::list(&hub.await, list.folder).await;
-            misc::print_list(files, json_output);
+            let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, list.drive).await;
+            let folder = drive::resolve_folder(&hub_tmp, list.folder).await;
+            if list.recursive {
+                misc::print_tree(drive::tree(&hub_tmp, folder).await, json_output, false);
+                exit(0);
+            }
+
+            let tags = parse_tags(&list.tag);
+            let mut files = drive::list(&hub_tmp, folder).await;
+            files.retain(|file| drive::has_tags(file, &tags));
+            if list.parts {
+                misc::print_list(files, json_output);
+            } else {
+                misc::print_logical_list(drive::group_files(files), json_output);
+            }
+            exit(0);
+        },
+        arguments::Commands::Find(find) => {
+            let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, find.drive).await;
+            let filter = drive::FindFilter {
+                folder_id: drive::resolve_folder(&hub_tmp, find.folder).await,
+                name_glob: find.name_glob,
+                modified_before: find.modified_before.as_deref().map(misc::parse_time),
+                larger_than: find.larger_than.as_deref().map(|size| misc::parse_data_size(size).as_u64()),
+                mime_type: find.mime.as_deref().map(drive::parse_mime_type),
+                trashed: find.trashed,
+                encrypted: find.encrypted,
+                tags: parse_tags(&find.tag),
+            };
+            misc::print_logical_list(drive::find(&hub_tmp, &filter).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Mkdir(mkdir) => {
+            let hub_tmp = hub.await;
+            let folder = drive::mkdir(&hub_tmp, &mkdir.path, mkdir.parents).await;
+            misc::print_list(vec![folder], json_output);
+            exit(0);
+        },
+        arguments::Commands::Quota => {
+            misc::print_quota(&drive::quota(&hub.await).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Du(du) => {
+            let hub_tmp = hub.await;
+            let folder = drive::resolve_folder(&hub_tmp, du.folder).await;
+            misc::print_disk_usage(drive::du(&hub_tmp, folder).await, json_output);
+            exit(0);
+        },
+        arguments::Commands::Tree(tree) => {
+            let hub_tmp = hub.await;
+            let folder = drive::resolve_folder(&hub_tmp, tree.folder).await;
+            misc::print_tree(drive::tree(&hub_tmp, folder).await, json_output, true);
             exit(0);
         },
         arguments::Commands::Download(download) => {
             let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, download.drive).await;
 
-            let info = drive::info(
+            let info = drive::resolve(
                 &hub_tmp,
                 &download.file
             ).await;
@@ -94,48 +338,282 @@ async fn main() {
 
             exit(0);
         },
+        arguments::Commands::Verify(verify) => {
+            let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, verify.drive).await;
+
+            let path = misc::config_file(verify.catalog, catalog::CATALOG_FILE);
+            let records = catalog::load(&path).unwrap_or_else(|e| {
+                error!("Failed at reading the catalog '{}' - {}", path.to_string_lossy(), e);
+                exit(misc::EXIT_CODE_031)
+            });
+
+            let info = drive::resolve(&hub_tmp, &verify.file).await;
+            let report = drive::verify(&hub_tmp, &info, verify.decrypt, &records).await;
+            misc::print_verify(&report, json_output);
+            exit(if report.passed { 0 } else { misc::EXIT_CODE_030 });
+        },
+        arguments::Commands::Push(push) => {
+            let part_size = push.part_size.as_deref().map(|size| misc::parse_data_size(size).as_u64());
+            if part_size == Some(0) {
+                error!("The part size cannot be 0 bytes");
+                exit(misc::EXIT_CODE_027)
+            }
+            if !Path::new(&push.dir).is_dir() {
+                error!("The path '{}' is not a folder", push.dir);
+                exit(misc::EXIT_CODE_027)
+            }
+
+            let options = drive::PushOptions {
+                part_size,
+                encryption_pub_key: if push.encrypt { Some(crypto::load_public_key(None)) } else { None },
+                filter: drive::MirrorFilter::new(&push.include, &push.exclude),
+                jobs: push.jobs,
+                delete: push.delete,
+                trash: push.trash,
+            };
+
+            let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, push.drive).await;
+            if push.dry_run {
+                // The destination is only created by the push, so a missing PATH is planned as an empty folder
+                let (folder, exists) = match push.dest {
+                    Some(path) if drive::is_path(&path) => match drive::find_folder(&hub_tmp, &path).await {
+                        Some(folder) => (Some(folder), true),
+                        None => {
+                            info!("The folder '{}' does not exist, it would be created (dry run)", path);
+                            (None, false)
+                        }
+                    },
+                    folder => (folder, true),
+                };
+                misc::print_sync_plan(drive::plan_push(&hub_tmp, Path::new(&push.dir), folder, exists, &options).await, json_output);
+                exit(0);
+            }
+
+            let folder = match push.dest {
+                Some(path) if drive::is_path(&path) => drive::mkdir(&hub_tmp, &path, true).await.id,
+                folder => folder,
+            };
+
+            let push_result = drive::push(&hub_tmp, Path::new(&push.dir), folder, &options).await;
+            let complete = push_result.complete;
+            misc::print_upload(push_result, json_output);
+            exit(if complete { 0 } else { misc::EXIT_CODE_021 });
+        },
+        arguments::Commands::Pull(pull) => {
+            let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, pull.drive).await;
+            let folder = drive::resolve_folder(&hub_tmp, Some(pull.folder)).await;
+
+            let filter = drive::MirrorFilter::new(&pull.include, &pull.exclude);
+            let entries = drive::pull(&hub_tmp, folder, Path::new(&pull.dir), &filter, pull.jobs).await;
+            misc::print_tree(entries, json_output, false);
+            exit(0);
+        },
         arguments::Commands::Upload(upload) => {
-            if atty::is(atty::Stream::Stdin) && !upload.testing {
+            // With a PATH, the data is read from the file (or a tar archive of it), instead of stdin
+            let mut source = upload.path.as_ref().map(|path| {
+                tar_stream::open_path(Path::new(path), upload.gzip).unwrap_or_else(|e| {
+                    error!("Failed at reading the path '{}' - {}", path, e);
+                    exit(misc::EXIT_CODE_026)
+                })
+            });
+
+            if atty::is(atty::Stream::Stdin) && !upload.testing && source.is_none() {
                 error!("You need to pipe something to this program otherwise it has nothing to upload");
                 exit(misc::EXIT_CODE_001);
             }
 
+            let size = match (upload.size.as_deref(), source.as_ref().and_then(|(_, size)| *size)) {
+                (Some(size), _) => misc::parse_data_size(size).as_u64(),
+                (None, Some(size)) => size,
+                // The chunks of a deduplicated upload are sized by the content
+                (None, None) if upload.dedup => 0,
+                (None, None) => {
+                    error!("The size of the data (--size) is required, it is only known when uploading a single file (--path)");
+                    exit(misc::EXIT_CODE_026)
+                },
+            };
+            if size == 0 && !upload.dedup {
+                error!("The size of the data cannot be 0 bytes");
+                exit(misc::EXIT_CODE_026)
+            }
+
+            let name = upload.filename.clone().unwrap_or_else(|| {
+                upload.path.as_deref().map_or("Untitled".to_owned(), |path| tar_stream::default_name(Path::new(path), upload.gzip))
+            });
+
             let mut encryption_pub_key = None;
             if upload.encrypt {
                 encryption_pub_key = Some(crypto::load_public_key(None))
             }
 
-            let upload_result: drive::UploadResult;
-            if upload.testing {
+            // The NAME is only expanded, if it is a valid template, to keep names containing a '%' working.
+            // A valid template is always expanded, so a '%%' becomes a '%' like the prune matcher expects
+            let template = retention::NameTemplate::parse(&name);
+            let filename = match template.as_ref() {
+                Ok(template) => template.expand(chrono::Local::now().naive_local()),
+                _ => name.clone(),
+            };
+
+            // The tags are saved as properties of the parts, and in the catalog
+            let tags: BTreeMap<String, String> = parse_tags(&upload.tag).into_iter().collect();
+            let properties: HashMap<String, String> = tags.clone().into_iter().collect();
+
+            let keep_policy = upload.keep.as_deref().map(|policy| {
+                if let Err(err) = template.as_ref() {
+                    error!("The name is not a valid template - {}", err);
+                    exit(misc::EXIT_CODE_020)
+                }
+                parse_keep_policy(policy)
+            });
+
+            let hub_tmp = hub.await;
+            drive::select_drive(&hub_tmp, upload.drive).await;
+            // With --mkdir the destination is always a PATH (also a single name without a '/')
+            let parent_folder = match upload.parent_folder {
+                Some(path) if upload.mkdir => drive::mkdir(&hub_tmp, &path, true).await.id,
+                parent_folder => drive::resolve_folder(&hub_tmp, parent_folder.or(profile_config.parent_folder)).await,
+            };
+
+            // Most of the chunks of a deduplicated upload are usually already uploaded
+            if !upload.no_quota_check && !upload.dedup {
+                drive::check_quota(&hub_tmp, size, upload.encrypt, upload.parity).await;
+            }
+
+            let options = drive::UploadOptions {
+                duplicate: upload.duplicate,
+                replace: upload.replace,
+                keep_partial: upload.keep_partial,
+                encryption_pub_key,
+                parity_parts: upload.parity,
+                properties,
+                existing_files: None,
+            };
+
+            let mut upload_result: drive::UploadResult;
+            if upload.dedup {
+                let chunk_folder = match upload.chunk_folder {
+                    Some(path) if drive::is_path(&path) => drive::mkdir(&hub_tmp, &path, true).await.id,
+                    chunk_folder => chunk_folder,
+                };
+                let reader: Box<dyn Read + Send> = if upload.testing {
+                    Box::new(TestBuffer::new(parse_data_size(upload.testing_size.as_str()).as_u64() as usize))
+                } else if let Some((reader, _)) = source.take() {
+                    reader
+                } else {
+                    Box::new(StdinWrapperWithSendSupport::new())
+                };
+
+                upload_result = drive::upload_dedup(
+                    &hub_tmp,
+                    reader,
+                    filename.clone(),
+                    parent_folder.clone(),
+                    chunk_folder,
+                    &options,
+                )
+                .await;
+            } else if upload.testing {
                 upload_result = drive::upload::<TestBuffer>(
-                    &hub.await,
+                    &hub_tmp,
                     TestBuffer::new(
                         parse_data_size(upload.testing_size.as_str()).as_u64() as usize,
                     ),
-                    misc::parse_data_size(upload.size.as_str()).as_u64() as usize,
-                    upload.filename,
-                    upload.parent_folder,
-                    upload.duplicate,
-                    upload.replace,
-                    encryption_pub_key,
+                    size as usize,
+                    filename.clone(),
+                    parent_folder.clone(),
+                    &options,
+                )
+                .await;
+            } else if let Some((reader, _)) = source.take() {
+                upload_result = drive::upload::<Box<dyn Read + Send>>(
+                    &hub_tmp,
+                    reader,
+                    size as usize,
+                    filename.clone(),
+                    parent_folder.clone(),
+                    &options,
                 )
                 .await;
             } else {
                 upload_result = drive::upload::<StdinWrapperWithSendSupport>(
-                    &hub.await,
+                    &hub_tmp,
                     StdinWrapperWithSendSupport::new(),
-                    misc::parse_data_size(upload.size.as_str()).as_u64() as usize,
-                    upload.filename,
-                    upload.parent_folder,
-                    upload.duplicate,
-                    upload.replace,
-                    encryption_pub_key,
+                    size as usize,
+                    filename.clone(),
+                    parent_folder.clone(),
+                    &options,
                 )
                 .await;
             }
 
+            if !upload_result.complete {
+                let exit_code = if upload_result.parity_failed { misc::EXIT_CODE_032 } else { misc::EXIT_CODE_021 };
+                misc::print_upload(upload_result, json_output);
+                exit(exit_code);
+            }
+
+            if !upload.no_catalog {
+                // Encrypted files are uploaded with the extension '.age' (but not the index of a deduplicated upload)
+                let mut stored_name = filename.clone();
+                if upload.encrypt && !upload.dedup {
+                    stored_name.push_str(".age");
+                }
+                let record = catalog::CatalogRecord::new(
+                    &upload_result, &stored_name, parent_folder.clone(), upload.encrypt, upload.dedup, tags);
+                let path = misc::config_file(upload.catalog, catalog::CATALOG_FILE);
+                match catalog::append(&path, &record) {
+                    Ok(()) => info!("Added '{}' to the catalog '{}'", stored_name, path.to_string_lossy()),
+                    Err(e) => warn!("Failed at adding '{}' to the catalog '{}' - {}", stored_name, path.to_string_lossy(), e),
+                }
+
+                if let Some(catalog_dest) = upload.catalog_dest {
+                    let folder = match catalog_dest {
+                        path if drive::is_path(&path) => drive::mkdir(&hub_tmp, &path, true).await.id,
+                        id => Some(id),
+                    };
+                    drive::upload_catalog(&hub_tmp, &path, folder).await;
+                }
+            }
+
+            if let Some(keep_policy) = keep_policy {
+                // Encrypted files are uploaded with the extension '.age' (but not the index of a deduplicated upload)
+                let mut template = name;
+                if upload.encrypt && !upload.dedup {
+                    template.push_str(".age");
+                }
+                let template = parse_name_template(&template);
+
+                for file in drive::prune(&hub_tmp, parent_folder, &template, &keep_policy, false, false).await {
+                    upload_result.deleted_files.extend(file.parts);
+                }
+            }
+
             misc::print_upload(upload_result, json_output);
             exit(0);
         },
     }
 }
+
+fn parse_name_template(template: &str) -> retention::NameTemplate {
+    retention::NameTemplate::parse(template).unwrap_or_else(|err| {
+        error!("The name is not a valid template - {}", err);
+        exit(misc::EXIT_CODE_020)
+    })
+}
+
+fn parse_keep_policy(policy: &str) -> retention::KeepPolicy {
+    retention::KeepPolicy::parse(policy).unwrap_or_else(|err| {
+        error!("The keep policy is invalid - {}", err);
+        exit(misc::EXIT_CODE_020)
+    })
+}
+
+fn parse_tags(tags: &[String]) -> Vec<(String, String)> {
+    tags.iter().map(|tag| catalog::parse_tag(tag).unwrap_or_else(|err| {
+        error!("{}", err);
+        exit(misc::EXIT_CODE_031)
+    })).collect()
+}
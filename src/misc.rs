@@ -1,11 +1,17 @@
 extern crate serde_json;
 
-use super::drive::UploadResult;
+use super::auth::AuthStatus;
+use super::catalog::CatalogRecord;
+use super::drive;
+use super::drive::{DiskUsage, LogicalFile, PlannedAction, Quota, SharedPermission, TreeEntry, UploadResult, VerifyReport};
+use super::profile;
 
 use regex::Regex;
 use bytesize::ByteSize;
-use google_drive3::api::File;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use google_drive3::api::{Drive, File};
 use prettytable::Table;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::exit;
 
@@ -26,8 +32,28 @@ pub const EXIT_CODE_011: i32 = 11;
 pub const EXIT_CODE_012: i32 = 12;
 pub const EXIT_CODE_013: i32 = 13;
 pub const EXIT_CODE_014: i32 = 14;
+pub const EXIT_CODE_015: i32 = 15;
+pub const EXIT_CODE_016: i32 = 16;
+pub const EXIT_CODE_017: i32 = 17;
+pub const EXIT_CODE_018: i32 = 18;
+pub const EXIT_CODE_019: i32 = 19;
+pub const EXIT_CODE_020: i32 = 20;
+pub const EXIT_CODE_021: i32 = 21;
+pub const EXIT_CODE_022: i32 = 22;
+pub const EXIT_CODE_023: i32 = 23;
+pub const EXIT_CODE_024: i32 = 24;
+pub const EXIT_CODE_025: i32 = 25;
+pub const EXIT_CODE_026: i32 = 26;
+pub const EXIT_CODE_027: i32 = 27;
+pub const EXIT_CODE_028: i32 = 28;
+pub const EXIT_CODE_029: i32 = 29;
+pub const EXIT_CODE_030: i32 = 30;
+pub const EXIT_CODE_031: i32 = 31;
+pub const EXIT_CODE_032: i32 = 32;
 
 lazy_static! {
+    static ref RE_RELATIVE_TIME: Regex = Regex::new(r"^(?P<count>\d+)\s*(?P<unit>[hHdDwW])$")
+        .expect("Something is wrong with your regular expression");
     static ref RE_BYTE: Regex = Regex::new(
         r"^(?x)
 (?P<size>\d+)  # the year
@@ -71,8 +97,8 @@ pub fn config_file(file: Option<String>, default: &str) -> PathBuf {
             }
         },
         None => match dirs::config_dir() {
-            Some(mut c) => {
-                c.push("pipe2drive");
+            Some(c) => {
+                let mut c = profile::config_dir(c);
                 ::std::fs::create_dir_all(c.as_path()).unwrap_or_else(|e| {
                     error!("{} - {}", e, c.to_string_lossy().to_string());
                     exit(EXIT_CODE_006)
@@ -102,6 +128,37 @@ pub fn file_filter(regex: &str, files: &Vec<File>) -> Vec<File> {
     filtered_files
 }
 
+// The first line of the description (of the last part) is the number of filler
+// bytes (0x00) appended to the file, the rest is the description set by the user
+pub fn parse_padding(description: &Option<String>) -> i64 {
+    padding_from_description(description).unwrap_or(0)
+}
+
+pub fn padding_from_description(description: &Option<String>) -> Option<i64> {
+    description.as_ref()
+        .and_then(|s| s.lines().next())
+        .and_then(|s| s.trim().parse::<i64>().ok())
+}
+
+pub fn build_description(padding: Option<i64>, text: Option<&str>) -> String {
+    match (padding, text) {
+        (Some(p), Some(t)) => format!("{}\n{}", p, t),
+        (Some(p), None) => p.to_string(),
+        (None, Some(t)) => t.to_owned(),
+        (None, None) => String::new(),
+    }
+}
+
+// Returns the suffix (e.g. '.001') if the name is a part of a file split into multiple files
+pub fn part_suffix(name: &str) -> Option<&str> {
+    let (_, ext) = name.rsplit_once('.')?;
+    if ext.len() >= 3 && ext.chars().all(|c| c.is_ascii_digit()) {
+        Some(&name[name.len() - ext.len() - 1..])
+    } else {
+        None
+    }
+}
+
 pub fn parse_data_size(size: &str) -> ByteSize {
     let caps = match RE_BYTE.captures(size) {
         Some(x) => x,
@@ -137,6 +194,33 @@ pub fn parse_data_size(size: &str) -> ByteSize {
     size
 }
 
+// Parses a point in time, either as RFC 3339 (2026-10-18T12:00:00Z), as a date (2026-10-18),
+// or relative to now in hours, days or weeks (12h, 30d or 4w)
+pub fn parse_time(time: &str) -> DateTime<Utc> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return time.with_timezone(&Utc);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    }
+
+    let caps = match RE_RELATIVE_TIME.captures(time) {
+        Some(x) => x,
+        None => {
+            error!("Unknown/Invalid format for: time");
+            exit(1);
+        }
+    };
+    let count = caps["count"].parse::<i64>().unwrap();
+    let duration = match caps["unit"].to_lowercase().as_str() {
+        "h" => Duration::hours(count),
+        "d" => Duration::days(count),
+        _ => Duration::weeks(count),
+    };
+
+    Utc::now() - duration
+}
+
 pub fn print_list(files: Vec<File>, json: bool) {
     if json {
         print!("{}", serde_json::to_string(&files).unwrap())
@@ -149,7 +233,7 @@ pub fn print_list(files: Vec<File>, json: bool) {
         for file in files {
             table.add_row(row![
                 file.id.unwrap(),
-                if file.mime_type.unwrap() == "application/vnd.google-apps.folder" {
+                if file.mime_type.unwrap() == drive::FOLDER_MIME_TYPE {
                     "Folder"
                 } else {
                     "File  "
@@ -161,11 +245,171 @@ pub fn print_list(files: Vec<File>, json: bool) {
     }
 }
 
+pub fn print_logical_list(files: Vec<LogicalFile>, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&files).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["ID", "Type", "Name", "Size", "Parts", "Encrypted", "Modified"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for file in files {
+            table.add_row(row![
+                file.id.as_ref().unwrap(),
+                if file.is_folder() { "Folder" } else { "File  " },
+                file.name,
+                file.size.map_or("".to_owned(), |s| ByteSize::b(s as u64).to_string()),
+                if file.parity.is_empty() {
+                    file.part_count.to_string()
+                } else {
+                    format!("{} (+{} parity)", file.part_count, file.parity.len())
+                },
+                if file.encrypted { "yes" } else { "no" },
+                file.modified_time.unwrap_or_default(),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
+// With `indent` the name is indented by the depth, otherwise the full path is shown
+pub fn print_tree(entries: Vec<TreeEntry>, json: bool, indent: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&entries).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["ID", "Type", if indent { "Name" } else { "Path" }, "Size", "Parts", "Encrypted", "Modified"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for entry in entries {
+            let file = entry.file;
+            table.add_row(row![
+                file.id.as_ref().unwrap(),
+                if file.is_folder() { "Folder" } else { "File  " },
+                if indent { format!("{}{}", "  ".repeat(entry.depth), file.name) } else { entry.path },
+                file.size.map_or("".to_owned(), |s| ByteSize::b(s as u64).to_string()),
+                file.part_count,
+                if file.encrypted { "yes" } else { "no" },
+                file.modified_time.unwrap_or_default(),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
+pub fn print_quota(quota: &Quota, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&quota).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["Variable", "Value"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        table.add_row(row!["limit", quota.limit.map_or("unlimited".to_owned(), |b| ByteSize::b(b).to_string())]);
+        table.add_row(row!["usage", ByteSize::b(quota.usage).to_string()]);
+        table.add_row(row!["usage_in_drive", ByteSize::b(quota.usage_in_drive).to_string()]);
+        table.add_row(row!["usage_in_drive_trash", ByteSize::b(quota.usage_in_drive_trash).to_string()]);
+        table.add_row(row!["free", quota.free.map_or("unlimited".to_owned(), |b| ByteSize::b(b).to_string())]);
+
+        table.printstd();
+    }
+}
+
+pub fn print_disk_usage(usages: Vec<DiskUsage>, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&usages).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["Size", "Files", "Path"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for usage in usages {
+            table.add_row(row![ByteSize::b(usage.size).to_string(), usage.file_count, usage.path]);
+        }
+        table.printstd();
+    }
+}
+
+pub fn print_sync_plan(actions: Vec<PlannedAction>, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&actions).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["Action", "Path", "Size", "ID"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for action in actions {
+            table.add_row(row![
+                action.action.as_str(),
+                action.path,
+                action.size.map_or("".to_owned(), |size| ByteSize::b(size).to_string()),
+                action.id.unwrap_or_default(),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
+pub fn print_drives(drives: Vec<Drive>, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&drives).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["ID", "Name", "Created"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for drive in drives {
+            table.add_row(row![
+                drive.id.unwrap(),
+                drive.name.unwrap_or_default(),
+                drive.created_time.unwrap_or_default(),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
+pub fn print_permissions(permissions: Vec<SharedPermission>, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&permissions).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["ID", "Type", "Role", "Grantee", "Parts"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for shared in permissions {
+            let permission = shared.permission;
+            let grantee = permission.email_address
+                .or(permission.domain)
+                .unwrap_or_else(|| "anyone with the link".to_owned());
+            table.add_row(row![
+                permission.id.unwrap_or_default(),
+                permission.type_.unwrap_or_default(),
+                permission.role.unwrap_or_default(),
+                grantee,
+                format!("{}/{}", shared.part_count, shared.total_part_count),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
 pub fn print_upload(upload_result: UploadResult, json: bool) {
     if json {
         print!("{}", serde_json::to_string(&upload_result).unwrap())
 
     } else {
+        if !upload_result.complete {
+            println!("The upload is incomplete");
+        }
+
         let mut table = Table::new();
         table.set_titles(row!["ID", "Name"]);
         table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
@@ -180,6 +424,104 @@ pub fn print_upload(upload_result: UploadResult, json: bool) {
     }
 }
 
+pub fn print_verify(report: &VerifyReport, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(report).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["Name", "ID", "Size", "MD5", "Result"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for part in &report.parts {
+            table.add_row(row![
+                part.name,
+                part.id.clone().unwrap_or_default(),
+                part.size.map_or("".to_owned(), |size| ByteSize::b(size).to_string()),
+                part.md5.clone().unwrap_or_default(),
+                if part.passed { "ok".to_owned() } else { part.problems.join(", ") },
+            ]);
+        }
+        table.printstd();
+
+        match report.decrypted {
+            Some(true) => println!("The decryption is ok"),
+            Some(false) | None => {
+                for problem in &report.problems {
+                    println!("Problem: {}", problem);
+                }
+            }
+        }
+        println!("The verification of '{}' {}", report.name, if report.passed { "passed" } else { "failed" });
+    }
+}
+
+pub fn print_catalog(records: Vec<CatalogRecord>, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&records).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["Uploaded", "Host", "Name", "Size", "Parts", "ID"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        for record in records {
+            table.add_row(row![
+                record.uploaded.format("%Y-%m-%d %H:%M:%S"),
+                record.host,
+                record.name,
+                ByteSize::b(record.size).to_string(),
+                record.parts.len(),
+                record.parts.first().map_or("".to_owned(), |part| part.id.clone()),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
+pub fn print_auth_status(status: &AuthStatus, json: bool) {
+    if json {
+        print!("{}", serde_json::to_string(&status).unwrap())
+
+    } else {
+        let mut table = Table::new();
+        table.set_titles(row!["Variable", "Value"]);
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+
+        let format_bytes = |v: &String| v.parse::<u64>().map_or(v.to_owned(), |b| ByteSize::b(b).to_string());
+
+        if let Some(ref v) = status.email {
+            table.add_row(row!["email", v]);
+        }
+        if let Some(ref v) = status.display_name {
+            table.add_row(row!["display_name", v]);
+        }
+        table.add_row(row!["scopes", status.scopes.join("\n")]);
+        if let Some(ref v) = status.token_expiry {
+            table.add_row(row!["token_expiry", v]);
+        }
+        table.add_row(row!["storage_limit", status.storage_limit.as_ref().map_or("unlimited".to_owned(), format_bytes)]);
+        if let Some(ref v) = status.storage_usage {
+            table.add_row(row!["storage_usage", format_bytes(v)]);
+        }
+        if let Some(ref v) = status.storage_usage_in_drive {
+            table.add_row(row!["storage_usage_in_drive", format_bytes(v)]);
+        }
+        if let Some(ref v) = status.storage_usage_in_drive_trash {
+            table.add_row(row!["storage_usage_in_drive_trash", format_bytes(v)]);
+        }
+
+        table.printstd();
+    }
+}
+
+// The properties as KEY=VALUE, sorted by the key
+fn format_properties(properties: &HashMap<String, String>) -> String {
+    let mut properties: Vec<String> = properties.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+    properties.sort();
+    properties.join(",")
+}
+
 pub fn print_info(file: &File, json: bool) {
     if json {
         print!("{}", serde_json::to_string(&file).unwrap())
@@ -231,9 +573,13 @@ pub fn print_info(file: &File, json: bool) {
         if let Some(ref v) = file.folder_color_rgb {
             table.add_row(row!["folder_color_rgb".to_owned(), v]);
         }
-        //    if let Some(ref v) = file.app_properties { table.add_row(row!["app_properties".to_owned(), v]); }
+        if let Some(ref v) = file.app_properties {
+            table.add_row(row!["app_properties".to_owned(), format_properties(v)]);
+        }
         //    if let Some(ref v) = file.capabilities { table.add_row(row!["capabilities".to_owned(), v]); }
-        //    if let Some(ref v) = file.properties { table.add_row(row!["properties".to_owned(), v]); }
+        if let Some(ref v) = file.properties {
+            table.add_row(row!["properties".to_owned(), format_properties(v)]);
+        }
         if let Some(ref v) = file.web_view_link {
             table.add_row(row!["web_view_link".to_owned(), v]);
         }
@@ -335,6 +681,29 @@ pub fn print_info(file: &File, json: bool) {
 
 #[cfg(test)]
 pub mod tests {
+    use super::{build_description, parse_padding, parse_time, part_suffix};
+
+    #[test]
+    fn test_010_description_padding() {
+        assert_eq!(0, parse_padding(&None));
+        assert_eq!(42, parse_padding(&Some("42".to_owned())));
+        assert_eq!(42, parse_padding(&Some("42\nnightly backup".to_owned())));
+        assert_eq!(0, parse_padding(&Some("nightly backup".to_owned())));
+
+        assert_eq!("42\nnightly backup", build_description(Some(42), Some("nightly backup")));
+        assert_eq!("42", build_description(Some(42), None));
+        assert_eq!("nightly backup", build_description(None, Some("nightly backup")));
+    }
+
+    #[test]
+    fn test_020_part_suffix() {
+        assert_eq!(Some(".000"), part_suffix("backup.tar.000"));
+        assert_eq!(Some(".1234"), part_suffix("backup.tar.age.1234"));
+        assert_eq!(None, part_suffix("backup.tar"));
+        assert_eq!(None, part_suffix("backup.01"));
+        assert_eq!(None, part_suffix("backup"));
+    }
+
     pub fn verify_test_buffer_data(buf: &[u8], start_no: u8, count: usize) -> bool {
         return verify_test_buffer_data_and_count_nulls(buf, start_no, count, false);
     }
@@ -377,4 +746,14 @@ pub mod tests {
 
         return true;
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_030_parse_time() {
+        assert_eq!("2026-10-18T12:00:00+00:00", parse_time("2026-10-18T14:00:00+02:00").to_rfc3339());
+        assert_eq!("2026-10-18T00:00:00+00:00", parse_time("2026-10-18").to_rfc3339());
+
+        let time = parse_time("30d");
+        let age = chrono::Utc::now() - time;
+        assert!(age >= chrono::Duration::days(30) && age < chrono::Duration::days(31));
+    }
+}
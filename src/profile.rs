@@ -0,0 +1,105 @@
+use super::misc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::OnceLock;
+
+pub const PROFILE_ENV: &str = "PIPE2DRIVE_PROFILE";
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILE_CONFIG_FILE: &str = "config.json";
+
+// The profile is selected once at startup and used by `misc::config_file`
+// for the rest of the runtime, so it does not have to be passed around
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+lazy_static! {
+    static ref RE_PROFILE_NAME: Regex = Regex::new(r"^[[:alnum:]][[:alnum:]_.-]*$")
+        .expect("Something is wrong with your regular expression");
+}
+
+// Defaults for a profile, the command line options always take precedence
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProfileConfig {
+    pub auth_flow: Option<String>,
+    pub scope: Option<String>,
+    pub service_account: Option<String>,
+    pub impersonate: Option<String>,
+    pub parent_folder: Option<String>,
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    RE_PROFILE_NAME.is_match(name)
+}
+
+pub fn init(profile: Option<String>) {
+    let profile = profile.unwrap_or_else(|| DEFAULT_PROFILE.to_owned());
+
+    if !is_valid_name(&profile) {
+        error!("The profile name '{}' is invalid, it may only contain letters, digits, '_', '-' and '.', \
+                and has to start with a letter or a digit", profile);
+        exit(misc::EXIT_CODE_015)
+    }
+
+    debug!("Using the profile: {}", profile);
+    if PROFILE.set(profile).is_err() {
+        panic!("The profile can only be selected once")
+    }
+}
+
+pub fn name() -> &'static str {
+    PROFILE.get().map_or(DEFAULT_PROFILE, |p| p.as_str())
+}
+
+// The default profile uses the config folder directly, to stay compatible with
+// the files created before profiles existed, all other profiles get a sub folder
+pub fn config_dir(mut config_dir: PathBuf) -> PathBuf {
+    config_dir.push("pipe2drive");
+    if name() != DEFAULT_PROFILE {
+        config_dir.push("profiles");
+        config_dir.push(name());
+    }
+    config_dir
+}
+
+pub fn load_config() -> ProfileConfig {
+    let path = misc::config_file(None, PROFILE_CONFIG_FILE);
+    if !path.exists() {
+        return ProfileConfig::default();
+    }
+
+    let data = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        error!("Failed at reading the profile config - Error: {err} - Path: {:?}", &path);
+        exit(misc::EXIT_CODE_015)
+    });
+    serde_json::from_str(&data).unwrap_or_else(|err| {
+        error!("The profile config is invalid - Error: {err} - Path: {:?}", &path);
+        exit(misc::EXIT_CODE_015)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_name, ProfileConfig};
+
+    #[test]
+    fn test_010_profile_names() {
+        assert!(is_valid_name("default"));
+        assert!(is_valid_name("company-shared.drive_2"));
+
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name(".."));
+        assert!(!is_valid_name("-work"));
+        assert!(!is_valid_name("work/../../etc"));
+    }
+
+    #[test]
+    fn test_020_profile_config_partial() {
+        let config: ProfileConfig = serde_json::from_str(r#"{"auth-flow": "device"}"#).unwrap();
+        assert_eq!(Some("device"), config.auth_flow.as_deref());
+        assert!(config.service_account.is_none());
+        assert!(config.parent_folder.is_none());
+    }
+}
@@ -0,0 +1,242 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use regex::Regex;
+
+// The date/time fields supported in a name template, e.g. 'db-%Y-%m-%d.tar'
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Field {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+// A file name containing date/time fields (strftime style), which is expanded when uploading
+// and used to find (and read the time of) the files a retention policy is applied to
+#[derive(Debug)]
+pub struct NameTemplate {
+    template: String,
+    regex: Regex,
+    fields: Vec<Field>,
+}
+
+impl NameTemplate {
+    pub fn parse(template: &str) -> Result<NameTemplate, String> {
+        let mut regex = String::from("^");
+        let mut fields = Vec::new();
+
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                regex.push_str(&regex::escape(&c.to_string()));
+                continue;
+            }
+
+            let spec = chars.next().unwrap_or(' ');
+            let spec_fields: &[Field] = match spec {
+                'Y' => &[Field::Year],
+                'm' => &[Field::Month],
+                'd' => &[Field::Day],
+                'H' => &[Field::Hour],
+                'M' => &[Field::Minute],
+                'S' => &[Field::Second],
+                'F' => &[Field::Year, Field::Month, Field::Day],
+                'T' => &[Field::Hour, Field::Minute, Field::Second],
+                '%' => {
+                    regex.push('%');
+                    continue;
+                }
+                _ => return Err(format!(
+                    "The field '%{}' is not supported, only %Y, %m, %d, %H, %M, %S, %F, %T and %% are", spec)),
+            };
+
+            for (index, field) in spec_fields.iter().enumerate() {
+                if index > 0 {
+                    regex.push(if spec == 'F' { '-' } else { ':' });
+                }
+                regex.push_str(if *field == Field::Year { r"(\d{4})" } else { r"(\d{2})" });
+                fields.push(*field);
+            }
+        }
+        regex.push('$');
+
+        Ok(NameTemplate {
+            template: template.to_owned(),
+            regex: Regex::new(&regex).expect("Something is wrong with your regular expression"),
+            fields,
+        })
+    }
+
+    pub fn has_fields(&self) -> bool {
+        !self.fields.is_empty()
+    }
+
+    pub fn expand(&self, time: NaiveDateTime) -> String {
+        time.format(&self.template).to_string()
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+
+    // The time in the name, the fields missing from the template defaults to the start of the period
+    pub fn timestamp(&self, name: &str) -> Option<NaiveDateTime> {
+        let caps = self.regex.captures(name)?;
+
+        let mut values = [None, Some(1), Some(1), Some(0), Some(0), Some(0)];
+        for (index, field) in self.fields.iter().enumerate() {
+            values[*field as usize] = caps[index + 1].parse::<u32>().ok();
+        }
+
+        NaiveDate::from_ymd_opt(values[0]? as i32, values[1]?, values[2]?)?
+            .and_hms_opt(values[3]?, values[4]?, values[5]?)
+    }
+}
+
+// How many files to keep, e.g. 'last=3,daily=7,weekly=4,monthly=12'. The newest file of each
+// hour/day/week/month/year is kept, for the given number of the newest periods (like `restic forget`)
+#[derive(Default, Debug, PartialEq)]
+pub struct KeepPolicy {
+    pub last: usize,
+    pub hourly: usize,
+    pub daily: usize,
+    pub weekly: usize,
+    pub monthly: usize,
+    pub yearly: usize,
+}
+
+impl KeepPolicy {
+    pub fn parse(policy: &str) -> Result<KeepPolicy, String> {
+        let mut keep_policy = KeepPolicy::default();
+
+        for rule in policy.split(',').map(|rule| rule.trim()).filter(|rule| !rule.is_empty()) {
+            let (period, count) = rule.split_once('=')
+                .ok_or_else(|| format!("The rule '{}' has to be in the format PERIOD=COUNT", rule))?;
+            let count = count.trim().parse::<usize>()
+                .map_err(|_| format!("The count of the rule '{}' is not a number", rule))?;
+
+            match period.trim() {
+                "last" => keep_policy.last = count,
+                "hourly" => keep_policy.hourly = count,
+                "daily" => keep_policy.daily = count,
+                "weekly" => keep_policy.weekly = count,
+                "monthly" => keep_policy.monthly = count,
+                "yearly" => keep_policy.yearly = count,
+                period => return Err(format!(
+                    "The period '{}' is unknown, use: last, hourly, daily, weekly, monthly or yearly", period)),
+            }
+        }
+
+        if keep_policy == KeepPolicy::default() {
+            return Err("The policy has to keep at least one file".to_owned());
+        }
+        Ok(keep_policy)
+    }
+
+    // Splits the files into the ones to keep and the ones to remove (both newest first)
+    pub fn apply<T>(&self, mut files: Vec<(NaiveDateTime, T)>) -> (Vec<T>, Vec<T>) {
+        files.sort_by(|(t1, _), (t2, _)| t2.cmp(t1));
+
+        let mut keep = vec![false; files.len()];
+        keep.iter_mut().take(self.last).for_each(|keep| *keep = true);
+
+        for (count, period_format) in [
+            (self.hourly, "%Y-%m-%d %H"),
+            (self.daily, "%Y-%m-%d"),
+            (self.weekly, "%G-%V"),
+            (self.monthly, "%Y-%m"),
+            (self.yearly, "%Y"),
+        ] {
+            let mut last_period = None;
+            let mut kept = 0;
+            for (index, (time, _)) in files.iter().enumerate() {
+                if kept == count {
+                    break;
+                }
+                let period = time.format(period_format).to_string();
+                if last_period.as_ref() != Some(&period) {
+                    keep[index] = true;
+                    kept += 1;
+                    last_period = Some(period);
+                }
+            }
+        }
+
+        let (kept, removed): (Vec<_>, Vec<_>) = files.into_iter()
+            .zip(keep)
+            .partition(|(_, keep)| *keep);
+        (
+            kept.into_iter().map(|((_, file), _)| file).collect(),
+            removed.into_iter().map(|((_, file), _)| file).collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeepPolicy, NameTemplate};
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn time(y: i32, m: u32, d: u32, h: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_010_name_template() {
+        let template = NameTemplate::parse("db-%F_%H.tar").unwrap();
+        assert!(template.has_fields());
+        assert_eq!("db-2026-10-18_04.tar", template.expand(time(2026, 10, 18, 4)));
+        assert_eq!(Some(time(2026, 10, 18, 4)), template.timestamp("db-2026-10-18_04.tar"));
+        assert!(!template.matches("db-2026-10-18_04.tar.gz"));
+        assert_eq!(None, template.timestamp("db-2026-13-18_04.tar"));
+
+        let template = NameTemplate::parse("100%%.txt").unwrap();
+        assert!(!template.has_fields());
+        assert!(template.matches("100%.txt"));
+
+        assert!(NameTemplate::parse("db-%s.tar").is_err());
+    }
+
+    #[test]
+    fn test_020_expand_and_match_template() {
+        let template = NameTemplate::parse("100%%-%F.txt").unwrap();
+        let name = template.expand(time(2026, 10, 18, 0));
+        assert_eq!("100%-2026-10-18.txt", name);
+        assert!(template.matches(&name));
+        assert_eq!(Some(time(2026, 10, 18, 0)), template.timestamp(&name));
+
+        let template = NameTemplate::parse("100%%.txt").unwrap();
+        assert!(template.matches(&template.expand(time(2026, 10, 18, 0))));
+    }
+
+    #[test]
+    fn test_030_keep_policy() {
+        assert_eq!(
+            KeepPolicy { last: 2, daily: 7, monthly: 12, ..Default::default() },
+            KeepPolicy::parse("last=2, daily=7,monthly=12").unwrap()
+        );
+        assert!(KeepPolicy::parse("").is_err());
+        assert!(KeepPolicy::parse("last=0").is_err());
+        assert!(KeepPolicy::parse("daily").is_err());
+        assert!(KeepPolicy::parse("minutely=5").is_err());
+    }
+
+    #[test]
+    fn test_040_apply_keep_policy() {
+        let files = vec![
+            (time(2026, 10, 18, 12), "18b"),
+            (time(2026, 10, 18, 4), "18a"),
+            (time(2026, 10, 17, 4), "17"),
+            (time(2026, 10, 16, 4), "16"),
+            (time(2026, 9, 30, 4), "09-30"),
+            (time(2026, 9, 1, 4), "09-01"),
+        ];
+
+        let policy = KeepPolicy { daily: 2, ..Default::default() };
+        assert_eq!((vec!["18b", "17"], vec!["18a", "16", "09-30", "09-01"]), policy.apply(files.clone()));
+
+        let policy = KeepPolicy { last: 3, monthly: 2, ..Default::default() };
+        assert_eq!((vec!["18b", "18a", "17", "09-30"], vec!["16", "09-01"]), policy.apply(files));
+    }
+}
@@ -0,0 +1,170 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::cmp;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+// How many chunks (of up to 1 MiB) the tar thread may be ahead of the upload
+const CHANNEL_CAPACITY: usize = 16;
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+// Streams a tar archive (optionally compressed with gzip) of a file or a folder, the archive is
+// created by a thread while it is read, so it never has to be written to the disk
+pub struct TarStream {
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+struct ChannelWriter {
+    sender: SyncSender<io::Result<Vec<u8>>>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender.send(Ok(buf.to_vec())).map_err(|_| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "The upload stopped reading the tar archive")
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl TarStream {
+    pub fn new(path: &Path, gzip: bool) -> TarStream {
+        let (sender, receiver) = sync_channel(CHANNEL_CAPACITY);
+        let path = path.to_owned();
+
+        thread::spawn(move || {
+            let error_sender = sender.clone();
+            let writer = BufWriter::with_capacity(CHUNK_SIZE, ChannelWriter { sender });
+
+            let result = if gzip {
+                write_tar(GzEncoder::new(writer, Compression::default()), &path)
+                    .and_then(|encoder| encoder.finish())
+                    .and_then(|mut writer| writer.flush())
+            } else {
+                write_tar(writer, &path).and_then(|mut writer| writer.flush())
+            };
+
+            if let Err(e) = result {
+                error!("Failed at creating the tar archive of '{}' - {}", path.to_string_lossy(), e);
+                let _ = error_sender.send(Err(e));
+            }
+        });
+
+        TarStream {
+            receiver,
+            chunk: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl Read for TarStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                Ok(Err(e)) => return Err(e),
+                // The thread is done, and all the data have been read
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let size = cmp::min(buf.len(), self.chunk.len() - self.position);
+        buf[..size].copy_from_slice(&self.chunk[self.position..self.position + size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+fn write_tar<W: Write>(writer: W, path: &Path) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+
+    let name = archive_name(path);
+    if path.is_dir() {
+        builder.append_dir_all(&name, path)?;
+    } else {
+        builder.append_path_with_name(path, &name)?;
+    }
+
+    builder.into_inner()
+}
+
+// The name of the file or folder in the archive (and the default name of the upload)
+fn archive_name(path: &Path) -> PathBuf {
+    fs::canonicalize(path).ok()
+        .and_then(|path| path.file_name().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("root"))
+}
+
+// A single regular file is uploaded as it is (with the size of it), everything else as a tar archive
+pub fn open_path(path: &Path, gzip: bool) -> io::Result<(Box<dyn Read + Send>, Option<u64>)> {
+    let metadata = fs::metadata(path)?;
+    if metadata.is_file() && !gzip {
+        return Ok((Box::new(fs::File::open(path)?), Some(metadata.len())));
+    }
+    Ok((Box::new(TarStream::new(path, gzip)), None))
+}
+
+pub fn default_name(path: &Path, gzip: bool) -> String {
+    let name = archive_name(path).to_string_lossy().to_string();
+    match (path.is_file(), gzip) {
+        (true, false) => name,
+        (_, false) => format!("{}.tar", name),
+        (_, true) => format!("{}.tar.gz", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_name, TarStream};
+    use flate2::read::GzDecoder;
+    use std::fs;
+    use std::io::Read;
+
+    #[test]
+    fn test_010_tar_stream() {
+        let dir = std::env::temp_dir().join(format!("pipe2drive-tar-stream-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "0123456789").unwrap();
+        fs::write(dir.join("sub").join("b.bin"), vec![7u8; 3 * 1024 * 1024]).unwrap();
+
+        let name = dir.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(format!("{}.tar.gz", name), default_name(&dir, true));
+
+        for gzip in [false, true] {
+            let mut data = Vec::new();
+            TarStream::new(&dir, gzip).read_to_end(&mut data).unwrap();
+
+            let reader: Box<dyn Read> = if gzip { Box::new(GzDecoder::new(&data[..])) } else { Box::new(&data[..]) };
+            let mut archive = tar::Archive::new(reader);
+            let mut sizes: Vec<(String, u64)> = archive.entries().unwrap()
+                .map(|entry| {
+                    let entry = entry.unwrap();
+                    (entry.path().unwrap().to_string_lossy().to_string(), entry.size())
+                })
+                .filter(|(_, size)| *size > 0)
+                .collect();
+            sizes.sort();
+
+            assert_eq!(vec![
+                (format!("{}/a.txt", name), 10),
+                (format!("{}/sub/b.bin", name), 3 * 1024 * 1024),
+            ], sizes);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}